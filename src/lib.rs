@@ -0,0 +1,944 @@
+//! Client for the Clash / mihomo external controller API.
+//!
+//! [`ClashClient`] wraps the REST endpoints (proxies, rules, connections,
+//! providers, config) and the streaming websockets; the response types
+//! mirror the controller's JSON. The `clashtui` binary is built on top of
+//! this crate.
+
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    thread,
+    time::{Duration, Instant},
+};
+use tungstenite::{
+    client::IntoClientRequest, connect, http::HeaderValue, stream::MaybeTlsStream, WebSocket,
+};
+
+/// How long to wait for the core to confirm a provider update
+pub const PROVIDER_UPDATE_TIMEOUT: Duration = Duration::from_secs(15);
+const PROVIDER_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// ============================================================================
+// Clash API Types
+// ============================================================================
+
+/// `GET /proxies`: every group and node keyed by name, in response order
+#[derive(Debug, Deserialize)]
+pub struct ProxiesResponse {
+    pub proxies: IndexMap<String, Proxy>,
+}
+
+/// A proxy node or group
+#[derive(Debug, Deserialize, Clone)]
+pub struct Proxy {
+    pub name: String,
+    /// Protocol (`Shadowsocks`, `Vmess`, ...) or group type (`Selector`, `URLTest`, ...)
+    #[serde(rename = "type")]
+    pub proxy_type: String,
+    /// Members of a group; empty for nodes
+    #[serde(default)]
+    pub all: Vec<String>,
+    /// Member currently used by a group
+    #[serde(default)]
+    pub now: Option<String>,
+    /// Recent delay tests, oldest first
+    #[serde(default)]
+    pub history: Vec<HistoryItem>,
+    /// Whether the node relays UDP
+    #[serde(default)]
+    pub udp: bool,
+}
+
+/// One delay test result
+#[derive(Debug, Deserialize, Clone)]
+pub struct HistoryItem {
+    /// Milliseconds; 0 when the test timed out
+    pub delay: i64,
+}
+
+/// `GET /rules`
+#[derive(Debug, Deserialize)]
+pub struct RulesResponse {
+    pub rules: Vec<Rule>,
+}
+
+/// A routing rule, e.g. `DOMAIN-SUFFIX,google.com → Proxy`
+#[derive(Debug, Deserialize, Clone)]
+pub struct Rule {
+    /// Matcher kind, e.g. `DomainSuffix` or `GeoIP`
+    #[serde(rename = "type")]
+    pub rule_type: String,
+    /// Value matched against, e.g. `google.com`; empty for `Match`
+    pub payload: String,
+    /// Target group or node
+    pub proxy: String,
+}
+
+/// `GET /version`
+#[derive(Debug, Deserialize)]
+pub struct VersionResponse {
+    pub version: String,
+    /// Set by Clash Meta / mihomo
+    #[serde(default)]
+    pub meta: bool,
+    /// Set by Clash Premium
+    #[serde(default)]
+    pub premium: bool,
+}
+
+/// Controller features that not every core provides
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `/connections`
+    pub connections: bool,
+    /// `/providers/rules`
+    pub rule_providers: bool,
+    /// `/group/{name}/delay`
+    pub group_delay: bool,
+}
+
+impl Default for Capabilities {
+    /// Unknown cores are assumed to support everything
+    fn default() -> Self {
+        Self {
+            connections: true,
+            rule_providers: true,
+            group_delay: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Known gaps per core flavor:
+    /// - Clash Meta / mihomo and Clash Premium: everything
+    /// - open-source Clash: no rule providers or group delay (Premium-only)
+    /// - open-source Clash before v0.17: no `/connections` either
+    ///
+    /// A version without a parsable `major.minor` (e.g. `unknown version`
+    /// from a self-built core) counts as unknown.
+    pub fn detect(version: &VersionResponse) -> Self {
+        if version.meta || version.premium {
+            return Self::default();
+        }
+        let mut numbers = version
+            .version
+            .trim_start_matches('v')
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|n| n.parse::<u32>());
+        let (Some(Ok(major)), Some(Ok(minor))) = (numbers.next(), numbers.next()) else {
+            return Self::default();
+        };
+        Self {
+            connections: (major, minor) >= (0, 17),
+            rule_providers: false,
+            group_delay: false,
+        }
+    }
+
+    /// Human-readable list of unavailable features
+    pub fn missing(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if !self.connections {
+            missing.push("connections");
+        }
+        if !self.rule_providers {
+            missing.push("rule providers");
+        }
+        if !self.group_delay {
+            missing.push("group delay tests");
+        }
+        missing
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DelayResponse {
+    delay: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct SelectProxyRequest {
+    name: String,
+}
+
+/// One sample from the `/traffic` websocket, in bytes per second
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Traffic {
+    pub up: u64,
+    pub down: u64,
+}
+
+/// `GET /connections`: active connections and cumulative traffic in bytes
+#[derive(Debug, Deserialize)]
+pub struct ConnectionsResponse {
+    #[serde(rename = "downloadTotal")]
+    pub download_total: u64,
+    #[serde(rename = "uploadTotal")]
+    pub upload_total: u64,
+    pub connections: Vec<Connection>,
+}
+
+/// An active connection
+#[derive(Debug, Deserialize, Clone)]
+pub struct Connection {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    /// Bytes sent so far
+    pub upload: u64,
+    /// Bytes received so far
+    pub download: u64,
+    /// Start time (RFC 3339)
+    pub start: String,
+    /// Proxies used, innermost node first and the rule's target last
+    pub chains: Vec<String>,
+    pub rule: String,
+    #[serde(default, rename = "rulePayload")]
+    pub rule_payload: String,
+}
+
+impl Connection {
+    /// The matched rule as `TYPE,PAYLOAD` (or just `TYPE` for payload-less rules)
+    pub fn rule_label(&self) -> String {
+        if self.rule_payload.is_empty() {
+            self.rule.clone()
+        } else {
+            format!("{},{}", self.rule, self.rule_payload)
+        }
+    }
+}
+
+/// Addressing details of a connection
+#[derive(Debug, Deserialize, Clone)]
+pub struct ConnectionMetadata {
+    /// `tcp` or `udp`
+    pub network: String,
+    /// Inbound the connection arrived on, e.g. `HTTP`, `Socks5` or `Tun`
+    #[serde(rename = "type")]
+    pub conn_type: String,
+    /// Client address
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    /// Resolved remote address; may be empty for domain requests
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    /// Client port, as a decimal string
+    #[serde(rename = "sourcePort")]
+    pub source_port: String,
+    /// Remote port, as a decimal string
+    #[serde(rename = "destinationPort")]
+    pub destination_port: String,
+    /// Requested domain; empty for plain IP connections
+    pub host: String,
+}
+
+/// `GET /configs`: the subset of the running config this crate uses
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConfigResponse {
+    pub mode: String,
+    #[serde(rename = "port")]
+    pub http_port: u16,
+    #[serde(rename = "socks-port")]
+    pub socks_port: u16,
+    #[serde(rename = "log-level", default)]
+    pub log_level: Option<String>,
+    #[serde(rename = "allow-lan", default)]
+    pub allow_lan: Option<bool>,
+    #[serde(rename = "bind-address", default)]
+    pub bind_address: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateConfigRequest {
+    mode: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProvidersResponse {
+    providers: HashMap<String, serde_json::Value>,
+}
+
+/// `GET /providers/proxies`, keyed by provider name
+#[derive(Debug, Deserialize)]
+pub struct ProxyProvidersResponse {
+    pub providers: HashMap<String, ProxyProvider>,
+}
+
+/// A proxy provider (subscription) and its nodes
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProxyProvider {
+    #[serde(default)]
+    pub name: String,
+    /// `HTTP` and `File` for subscriptions, `Compatible` for a group's own list
+    #[serde(rename = "vehicleType", default)]
+    pub vehicle_type: String,
+    /// Time of the last successful update
+    #[serde(rename = "updatedAt", default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub proxies: Vec<Proxy>,
+}
+
+/// Group names in config order. The core lists top-level groups and nodes
+/// in config order under GLOBAL; groups it omits follow in response order,
+/// and GLOBAL itself comes last.
+pub fn ordered_groups(proxies: &IndexMap<String, Proxy>) -> Vec<String> {
+    let is_group = |name: &str| {
+        proxies.get(name).is_some_and(|p| {
+            p.proxy_type == "Selector" || p.proxy_type == "URLTest" || p.proxy_type == "Fallback"
+        })
+    };
+    let mut groups: Vec<String> = proxies
+        .get("GLOBAL")
+        .map(|g| g.all.iter().filter(|n| is_group(n)).cloned().collect())
+        .unwrap_or_default();
+    for name in proxies.keys() {
+        if name != "GLOBAL" && is_group(name) && !groups.contains(name) {
+            groups.push(name.clone());
+        }
+    }
+    if is_group("GLOBAL") {
+        groups.push("GLOBAL".to_string());
+    }
+    groups
+}
+
+/// Follow `now` from `group` through nested groups until it reaches a
+/// concrete node. Returns every hop after `group`; stops early on cycles.
+pub fn resolve_now_chain(all_proxies: &IndexMap<String, Proxy>, group: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut visited: HashSet<&str> = HashSet::from([group]);
+    let mut current = all_proxies.get(group).and_then(|g| g.now.as_deref());
+    while let Some(name) = current {
+        if !visited.insert(name) {
+            break;
+        }
+        chain.push(name.to_string());
+        current = all_proxies.get(name).and_then(|p| p.now.as_deref());
+    }
+    chain
+}
+
+/// Latest delay for `name` as seen from `group`. The core keeps a
+/// provider-scoped node list per group, so prefer that history over the
+/// global name-keyed map, which cannot tell same-named nodes apart.
+pub fn node_delay(
+    all_proxies: &IndexMap<String, Proxy>,
+    providers: &[ProxyProvider],
+    group: &str,
+    name: &str,
+) -> i64 {
+    let scoped = providers
+        .iter()
+        .find(|p| p.name == group)
+        .and_then(|p| p.proxies.iter().find(|n| n.name == name));
+    scoped
+        .or_else(|| all_proxies.get(name))
+        .and_then(|p| p.history.last())
+        .map(|h| h.delay)
+        .unwrap_or(-1)
+}
+
+/// Node names that appear in more than one subscription provider, whose
+/// global delay may belong to either node.
+pub fn ambiguous_node_names(providers: &[ProxyProvider]) -> HashSet<String> {
+    let mut seen: HashMap<&str, &str> = HashMap::new();
+    let mut ambiguous = HashSet::new();
+    // Compatible providers mirror groups and re-list nodes from elsewhere
+    for provider in providers.iter().filter(|p| p.vehicle_type != "Compatible") {
+        for node in &provider.proxies {
+            match seen.get(node.name.as_str()) {
+                Some(owner) if *owner != provider.name => {
+                    ambiguous.insert(node.name.clone());
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(&node.name, &provider.name);
+                }
+            }
+        }
+    }
+    ambiguous
+}
+
+// ============================================================================
+// Clash API Client
+// ============================================================================
+
+/// Blocking client for one controller. Cheap to clone, so background
+/// threads can each take their own copy.
+#[derive(Clone)]
+pub struct ClashClient {
+    base_url: String,
+    secret: Option<String>,
+    client: reqwest::blocking::Client,
+    // Connect and request timeout for ordinary API calls
+    timeout: Duration,
+}
+
+/// HTTP client that gives up connecting or waiting for a response after
+/// `timeout`, so a stalled controller cannot freeze the UI.
+fn http_client(timeout: Duration) -> reqwest::blocking::Client {
+    reqwest::blocking::Client::builder()
+        .connect_timeout(timeout)
+        .timeout(timeout)
+        .build()
+        .unwrap_or_else(|_| reqwest::blocking::Client::new())
+}
+
+/// Describe timeouts and refused connections in plain words
+fn request_error(err: reqwest::Error) -> anyhow::Error {
+    if err.is_timeout() {
+        anyhow!("the controller did not respond in time")
+    } else if err.is_connect() {
+        anyhow!("cannot connect to the controller")
+    } else {
+        err.into()
+    }
+}
+
+impl ClashClient {
+    /// `controller` is `host:port` or a full `http(s)://` URL, optionally
+    /// with a path prefix for controllers behind a reverse proxy
+    /// (`https://host/clash/`); `secret` is sent as a Bearer token.
+    pub fn new(controller: &str, secret: Option<String>, timeout: Duration) -> Self {
+        let base_url = if controller.starts_with("http") {
+            controller.to_string()
+        } else {
+            format!("http://{}", controller)
+        };
+        // Endpoints start with `/`, so keep the prefix without a trailing one
+        let base_url = base_url.trim_end_matches('/').to_string();
+
+        Self {
+            base_url,
+            secret,
+            client: http_client(timeout),
+            timeout,
+        }
+    }
+
+    /// Copy of the client that gives up after `timeout`
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            client: http_client(timeout),
+            timeout,
+            ..self
+        }
+    }
+
+    /// Timeout applied to ordinary API calls
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Timeout for calls where the core itself probes or downloads
+    fn slow_timeout(&self, probe: Duration) -> Duration {
+        probe + self.timeout
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+    ) -> reqwest::blocking::RequestBuilder {
+        let url = self.url(endpoint);
+        let mut req = self.client.request(method, &url);
+        if let Some(ref secret) = self.secret {
+            req = req.header("Authorization", format!("Bearer {}", secret));
+        }
+        req
+    }
+
+    /// REST URL for an endpoint such as `/proxies`, under the base path
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}{}", self.base_url, endpoint)
+    }
+
+    /// WebSocket URL for an endpoint, derived from the REST base URL
+    fn ws_url(&self, endpoint: &str) -> String {
+        let url = self.url(endpoint);
+        if let Some(rest) = url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            url
+        }
+    }
+
+    /// Open a websocket to `endpoint`. With a secret, the Bearer header is
+    /// tried first and the `?token=` query second, since forks accept one or
+    /// the other. Returns the socket and the auth method that succeeded.
+    pub fn connect_ws(
+        &self,
+        endpoint: &str,
+    ) -> Result<(WebSocket<MaybeTlsStream<std::net::TcpStream>>, &'static str)> {
+        let url = self.ws_url(endpoint);
+        let Some(ref secret) = self.secret else {
+            let (socket, _) = connect(&url).context("Failed to open websocket")?;
+            return Ok((socket, "none"));
+        };
+
+        // A secret that is not a valid header value falls through to the
+        // query token as well
+        let header = HeaderValue::from_str(&format!("Bearer {}", secret));
+        if let (Ok(mut req), Ok(header)) = (url.as_str().into_client_request(), header) {
+            req.headers_mut().insert("Authorization", header);
+            if let Ok((socket, _)) = connect(req) {
+                return Ok((socket, "header"));
+            }
+        }
+
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let token_url = format!("{}{}token={}", url, separator, urlencoding::encode(secret));
+        let (socket, _) = connect(&token_url).context("Failed to open websocket")?;
+        Ok((socket, "token"))
+    }
+
+    /// All groups and nodes
+    pub fn get_proxies(&self) -> Result<ProxiesResponse> {
+        let resp = self
+            .request(reqwest::Method::GET, "/proxies")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch proxies")?
+            .json::<ProxiesResponse>()
+            .context("Failed to parse proxies response")?;
+        Ok(resp)
+    }
+
+    /// Routing rules in match order
+    pub fn get_rules(&self) -> Result<RulesResponse> {
+        let resp = self
+            .request(reqwest::Method::GET, "/rules")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch rules")?
+            .json::<RulesResponse>()
+            .context("Failed to parse rules response")?;
+        Ok(resp)
+    }
+
+    /// Core version and flavor
+    pub fn get_version(&self) -> Result<VersionResponse> {
+        let resp = self
+            .request(reqwest::Method::GET, "/version")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch version")?
+            .json::<VersionResponse>()
+            .context("Failed to parse version response")?;
+        Ok(resp)
+    }
+
+    /// Make `proxy` the selected member of `group`
+    pub fn select_proxy(&self, group: &str, proxy: &str) -> Result<()> {
+        let endpoint = format!("/proxies/{}", urlencoding::encode(group));
+        self.request(reqwest::Method::PUT, &endpoint)
+            .json(&SelectProxyRequest {
+                name: proxy.to_string(),
+            })
+            .send()
+            .map_err(request_error)
+            .context("Failed to select proxy")?;
+        Ok(())
+    }
+
+    /// Delay of one node through `url` in milliseconds, or -1 on timeout
+    pub fn test_delay(&self, proxy: &str, url: &str, timeout: u64) -> Result<i64> {
+        let endpoint = format!(
+            "/proxies/{}/delay?url={}&timeout={}",
+            urlencoding::encode(proxy),
+            urlencoding::encode(url),
+            timeout
+        );
+        let resp = self
+            .request(reqwest::Method::GET, &endpoint)
+            .timeout(self.slow_timeout(Duration::from_millis(timeout)))
+            .send()
+            .map_err(request_error)
+            .context("Failed to test delay")?;
+
+        if resp.status().is_success() {
+            let delay_resp: DelayResponse =
+                resp.json().context("Failed to parse delay response")?;
+            Ok(delay_resp.delay)
+        } else {
+            Ok(-1) // Timeout or error
+        }
+    }
+
+    /// Ask the core to test every node of a group; URLTest/Fallback groups
+    /// re-elect their node afterwards. Returns delays by node name.
+    pub fn test_group(&self, group: &str, url: &str, timeout: u64) -> Result<HashMap<String, i64>> {
+        let endpoint = format!(
+            "/group/{}/delay?url={}&timeout={}",
+            urlencoding::encode(group),
+            urlencoding::encode(url),
+            timeout
+        );
+        let resp = self
+            .request(reqwest::Method::GET, &endpoint)
+            .timeout(self.slow_timeout(Duration::from_millis(timeout)))
+            .send()
+            .map_err(request_error)
+            .context("Failed to test group")?
+            .json::<HashMap<String, i64>>()
+            .context("Failed to parse group delay response")?;
+        Ok(resp)
+    }
+
+    /// Running config (mode, ports, ...)
+    pub fn get_config(&self) -> Result<ConfigResponse> {
+        let resp = self
+            .request(reqwest::Method::GET, "/configs")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch config")?
+            .json::<ConfigResponse>()
+            .context("Failed to parse config response")?;
+        Ok(resp)
+    }
+
+    /// Active connections and traffic totals
+    pub fn get_connections(&self) -> Result<ConnectionsResponse> {
+        let resp = self
+            .request(reqwest::Method::GET, "/connections")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch connections")?
+            .json::<ConnectionsResponse>()
+            .context("Failed to parse connections response")?;
+        Ok(resp)
+    }
+
+    /// Close one connection by id
+    pub fn close_connection(&self, id: &str) -> Result<()> {
+        self.request(reqwest::Method::DELETE, &format!("/connections/{}", id))
+            .send()
+            .map_err(request_error)
+            .context("Failed to close connection")?
+            .error_for_status()
+            .context("Failed to close connection")?;
+        Ok(())
+    }
+
+    /// Close every active connection
+    pub fn close_all_connections(&self) -> Result<()> {
+        self.request(reqwest::Method::DELETE, "/connections")
+            .send()
+            .map_err(request_error)
+            .context("Failed to close connections")?
+            .error_for_status()
+            .context("Failed to close connections")?;
+        Ok(())
+    }
+
+    /// Switch the routing mode (`Rule`, `Global` or `Direct`)
+    pub fn update_mode(&self, mode: &str) -> Result<()> {
+        let req = UpdateConfigRequest {
+            mode: mode.to_string(),
+        };
+        self.request(reqwest::Method::PATCH, "/configs")
+            .json(&req)
+            .send()
+            .map_err(request_error)
+            .context("Failed to update config")?;
+        Ok(())
+    }
+
+    /// Names of the proxy providers
+    pub fn get_proxy_provider_names(&self) -> Result<Vec<String>> {
+        let resp = self
+            .request(reqwest::Method::GET, "/providers/proxies")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch proxy providers")?
+            .json::<ProvidersResponse>()
+            .context("Failed to parse proxy providers response")?;
+
+        let mut names: Vec<String> = resp.providers.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Names of the rule providers
+    pub fn get_rule_provider_names(&self) -> Result<Vec<String>> {
+        let resp = self
+            .request(reqwest::Method::GET, "/providers/rules")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch rule providers")?
+            .json::<ProvidersResponse>()
+            .context("Failed to parse rule providers response")?;
+
+        let mut names: Vec<String> = resp.providers.keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Ask the core to re-download a proxy provider
+    pub fn update_proxy_provider(&self, provider_name: &str) -> Result<()> {
+        let endpoint = format!("/providers/proxies/{}", urlencoding::encode(provider_name));
+        self.request(reqwest::Method::PUT, &endpoint)
+            .timeout(self.slow_timeout(PROVIDER_UPDATE_TIMEOUT))
+            .send()
+            .map_err(request_error)
+            .with_context(|| format!("Failed to update proxy provider {}", provider_name))?;
+        Ok(())
+    }
+
+    /// Delay-test every node of a proxy provider
+    pub fn healthcheck_proxy_provider(&self, provider_name: &str) -> Result<()> {
+        let endpoint = format!(
+            "/providers/proxies/{}/healthcheck",
+            urlencoding::encode(provider_name)
+        );
+        self.request(reqwest::Method::GET, &endpoint)
+            .timeout(self.slow_timeout(PROVIDER_UPDATE_TIMEOUT))
+            .send()
+            .map_err(request_error)
+            .with_context(|| format!("Failed to health-check proxy provider {}", provider_name))?;
+        Ok(())
+    }
+
+    /// Health-check every proxy provider, calling `progress(index, total, name)`
+    /// before each one. Returns the number checked and the failures.
+    pub fn healthcheck_all_providers(
+        &self,
+        mut progress: impl FnMut(usize, usize, &str),
+    ) -> Result<(usize, Vec<String>)> {
+        let provider_names = self.get_proxy_provider_names()?;
+        let total = provider_names.len();
+        let mut checked_count: usize = 0;
+        let mut failed_items: Vec<String> = Vec::new();
+
+        for (idx, provider_name) in provider_names.iter().enumerate() {
+            progress(idx + 1, total, provider_name);
+            if let Err(err) = self.healthcheck_proxy_provider(provider_name) {
+                failed_items.push(format!("{} ({})", provider_name, err));
+            } else {
+                checked_count += 1;
+            }
+        }
+        Ok((checked_count, failed_items))
+    }
+
+    /// All proxy providers with their nodes
+    pub fn get_proxy_providers(&self) -> Result<ProxyProvidersResponse> {
+        let resp = self
+            .request(reqwest::Method::GET, "/providers/proxies")
+            .send()
+            .map_err(request_error)
+            .context("Failed to fetch proxy providers")?
+            .json::<ProxyProvidersResponse>()
+            .context("Failed to parse proxy providers response")?;
+        Ok(resp)
+    }
+
+    /// One proxy provider with its nodes
+    pub fn get_proxy_provider(&self, provider_name: &str) -> Result<ProxyProvider> {
+        let endpoint = format!("/providers/proxies/{}", urlencoding::encode(provider_name));
+        let resp = self
+            .request(reqwest::Method::GET, &endpoint)
+            .send()
+            .map_err(request_error)
+            .with_context(|| format!("Failed to fetch proxy provider {}", provider_name))?
+            .json::<ProxyProvider>()
+            .context("Failed to parse proxy provider response")?;
+        Ok(resp)
+    }
+
+    /// Update a proxy provider and wait until its `updatedAt` changes.
+    /// Returns false if the core has not confirmed the update within `timeout`.
+    pub fn update_proxy_provider_and_wait(
+        &self,
+        provider_name: &str,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let before = self.get_proxy_provider(provider_name).ok();
+        self.update_proxy_provider(provider_name)?;
+
+        // Only subscription (HTTP) providers fetch remotely; others refresh at once
+        let Some(before) = before.filter(|p| p.vehicle_type == "HTTP") else {
+            return Ok(true);
+        };
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Ok(after) = self.get_proxy_provider(provider_name) {
+                if after.updated_at != before.updated_at {
+                    return Ok(true);
+                }
+            }
+            thread::sleep(PROVIDER_POLL_INTERVAL);
+        }
+        Ok(false)
+    }
+
+    /// Ask the core to re-download a rule provider
+    pub fn update_rule_provider(&self, provider_name: &str) -> Result<()> {
+        let endpoint = format!("/providers/rules/{}", urlencoding::encode(provider_name));
+        self.request(reqwest::Method::PUT, &endpoint)
+            .timeout(self.slow_timeout(PROVIDER_UPDATE_TIMEOUT))
+            .send()
+            .map_err(request_error)
+            .with_context(|| format!("Failed to update rule provider {}", provider_name))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(controller: &str) -> ClashClient {
+        ClashClient::new(controller, None, Duration::from_secs(1))
+    }
+
+    #[test]
+    fn endpoint_urls_respect_base_path() {
+        let cases = [
+            ("127.0.0.1:9090", "http://127.0.0.1:9090/proxies"),
+            ("127.0.0.1:9090/", "http://127.0.0.1:9090/proxies"),
+            ("http://127.0.0.1:9090", "http://127.0.0.1:9090/proxies"),
+            ("https://host/clash", "https://host/clash/proxies"),
+            ("https://host/clash/", "https://host/clash/proxies"),
+            ("host:8080/a/b//", "http://host:8080/a/b/proxies"),
+        ];
+        for (controller, expected) in cases {
+            assert_eq!(
+                client(controller).url("/proxies"),
+                expected,
+                "{}",
+                controller
+            );
+        }
+    }
+
+    #[test]
+    fn websocket_urls_respect_base_path() {
+        assert_eq!(
+            client("https://host/clash/").ws_url("/traffic"),
+            "wss://host/clash/traffic"
+        );
+        assert_eq!(
+            client("127.0.0.1:9090").ws_url("/traffic"),
+            "ws://127.0.0.1:9090/traffic"
+        );
+    }
+
+    fn version(version: &str, meta: bool, premium: bool) -> VersionResponse {
+        VersionResponse {
+            version: version.to_string(),
+            meta,
+            premium,
+        }
+    }
+
+    #[test]
+    fn capabilities_follow_core_flavor_and_version() {
+        let everything = Capabilities::default();
+        assert_eq!(
+            Capabilities::detect(&version("v1.18.0", true, false)),
+            everything
+        );
+        assert_eq!(
+            Capabilities::detect(&version("2023.08.17", false, true)),
+            everything
+        );
+
+        let old = Capabilities::detect(&version("v0.16.3", false, false));
+        assert!(!old.connections && !old.rule_providers && !old.group_delay);
+
+        let open_source = Capabilities::detect(&version("v1.18.0", false, false));
+        assert!(open_source.connections);
+        assert!(!open_source.rule_providers && !open_source.group_delay);
+
+        assert_eq!(
+            Capabilities::detect(&version("unknown version", false, false)),
+            everything
+        );
+    }
+
+    fn node(name: &str, delays: &[i64]) -> Proxy {
+        Proxy {
+            name: name.to_string(),
+            proxy_type: "Shadowsocks".to_string(),
+            all: Vec::new(),
+            now: None,
+            history: delays.iter().map(|&delay| HistoryItem { delay }).collect(),
+            udp: false,
+        }
+    }
+
+    fn provider(name: &str, vehicle_type: &str, proxies: Vec<Proxy>) -> ProxyProvider {
+        ProxyProvider {
+            name: name.to_string(),
+            vehicle_type: vehicle_type.to_string(),
+            updated_at: None,
+            proxies,
+        }
+    }
+
+    #[test]
+    fn duplicate_names_use_group_scoped_delay() {
+        // The global map only keeps one of the two "HK 01" nodes
+        let all_proxies = IndexMap::from([("HK 01".to_string(), node("HK 01", &[80]))]);
+        let providers = vec![
+            provider("sub-a", "HTTP", vec![node("HK 01", &[80])]),
+            provider("sub-b", "HTTP", vec![node("HK 01", &[450])]),
+            provider("Group B", "Compatible", vec![node("HK 01", &[120, 450])]),
+        ];
+
+        assert_eq!(
+            node_delay(&all_proxies, &providers, "Group B", "HK 01"),
+            450
+        );
+        // Groups without a scoped list fall back to the global entry
+        assert_eq!(node_delay(&all_proxies, &providers, "Group A", "HK 01"), 80);
+        assert_eq!(
+            node_delay(&all_proxies, &providers, "Group A", "missing"),
+            -1
+        );
+
+        let ambiguous = ambiguous_node_names(&providers);
+        assert!(ambiguous.contains("HK 01"));
+        assert_eq!(ambiguous.len(), 1);
+    }
+
+    #[test]
+    fn group_order_is_stable_across_parses() {
+        let json = r#"{"proxies": {
+            "GLOBAL": {"name": "GLOBAL", "type": "Selector", "all": ["Proxy", "Auto", "HK 01"], "now": "Proxy"},
+            "Auto": {"name": "Auto", "type": "URLTest", "all": ["HK 01"], "now": "HK 01"},
+            "HK 01": {"name": "HK 01", "type": "Shadowsocks"},
+            "Streaming": {"name": "Streaming", "type": "Selector", "all": ["Auto"]},
+            "Proxy": {"name": "Proxy", "type": "Selector", "all": ["Auto", "HK 01"], "now": "Auto"}
+        }}"#;
+
+        let first: ProxiesResponse = serde_json::from_str(json).unwrap();
+        let expected = ["Proxy", "Auto", "Streaming", "GLOBAL"];
+        assert_eq!(ordered_groups(&first.proxies), expected);
+        for _ in 0..10 {
+            let again: ProxiesResponse = serde_json::from_str(json).unwrap();
+            assert!(again.proxies.keys().eq(first.proxies.keys()));
+            assert_eq!(ordered_groups(&again.proxies), expected);
+        }
+    }
+
+    #[test]
+    fn compatible_providers_do_not_flag_names() {
+        let providers = vec![
+            provider(
+                "sub-a",
+                "HTTP",
+                vec![node("JP 01", &[]), node("JP 02", &[])],
+            ),
+            provider("Group", "Compatible", vec![node("JP 01", &[])]),
+        ];
+        assert!(ambiguous_node_names(&providers).is_empty());
+    }
+}
@@ -1,28 +1,47 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use clashtui::{
+    ambiguous_node_names, node_delay, ordered_groups, resolve_now_chain, Capabilities, ClashClient,
+    ConfigResponse, Connection, ConnectionMetadata, ConnectionsResponse, HistoryItem,
+    ProxiesResponse, Proxy, ProxyProvider, ProxyProvidersResponse, Rule, RulesResponse, Traffic,
+    PROVIDER_UPDATE_TIMEOUT,
+};
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use indexmap::IndexMap;
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Tabs},
     Frame, Terminal,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, io, thread, time::Duration};
-use tungstenite::connect;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    io::{self, BufRead, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 // ============================================================================
 // CLI Arguments
 // ============================================================================
 
 #[derive(Parser)]
-#[command(name = "clashtui")]
+#[command(name = "clashtui", version)]
 #[command(about = "A TUI tool for managing Clash proxies and rules", long_about = None)]
 struct Cli {
     /// Clash external controller address
@@ -33,6 +52,38 @@ struct Cli {
     #[arg(short, long)]
     secret: Option<String>,
 
+    /// Read the API secret from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "secret")]
+    secret_file: Option<PathBuf>,
+
+    /// Append timestamped status messages to a log file
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Periodically append traffic stats to a CSV (or .jsonl) file
+    #[arg(long, value_name = "PATH")]
+    stats_file: Option<PathBuf>,
+
+    /// Seconds between stats samples
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    stats_interval: u64,
+
+    /// Browse only: disable proxy selection, mode changes and provider updates
+    #[arg(long)]
+    read_only: bool,
+
+    /// Leave mouse events to the terminal so native text selection works
+    #[arg(long)]
+    no_mouse: bool,
+
+    /// Seconds to wait for the controller before giving up on a request
+    #[arg(long, value_name = "SECS", default_value_t = 5)]
+    timeout: u64,
+
+    /// Print machine-readable JSON (status command)
+    #[arg(long, global = true)]
+    json: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -45,311 +96,585 @@ enum Commands {
     Rules,
     /// List all proxy groups
     Groups,
-    /// Show Clash version
+    /// Show the clashtui and Clash core versions
     Version,
     /// Interactive TUI mode (default)
     Tui,
     /// Refresh subscriptions (proxy/rule providers)
     Refresh,
+    /// One-shot summary of mode, ports, connections and traffic
+    Status,
+    /// Serve Prometheus metrics over HTTP
+    Metrics {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:9898")]
+        listen: String,
+    },
 }
 
 // ============================================================================
-// Clash API Types
+// Config & State
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
-struct ProxiesResponse {
-    proxies: HashMap<String, Proxy>,
-}
+const DEFAULT_TEST_URL: &str = "http://www.gstatic.com/generate_204";
+const TEST_TIMEOUT_MS: u64 = 5000;
+/// Maximum number of delay tests in flight during a group test
+const TEST_CONCURRENCY: usize = 8;
+/// Groups tested more recently than this are not auto-tested again
+const AUTO_TEST_TTL_SECS: u64 = 600;
+/// Width of the groups pane in the Proxies tab, in percent
+const DEFAULT_GROUP_PANE_PCT: u16 = 35;
+const MIN_GROUP_PANE_PCT: u16 = 15;
+const MAX_GROUP_PANE_PCT: u16 = 70;
+const GROUP_PANE_STEP: u16 = 5;
+/// Full scale of the traffic gauges, in megabits per second
+const DEFAULT_MAX_BANDWIDTH_MBPS: u64 = 100;
+/// Traffic samples kept (one per second from the traffic websocket)
+const DEFAULT_HISTORY_SAMPLES: usize = 60;
+/// Samples averaged when smoothing is enabled
+const SMOOTHING_WINDOW: usize = 5;
+/// Request timeout for the one-shot status command
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+/// Window for the second Enter that confirms a pick in an auto group
+const SELECT_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+/// How long a tab's data is shown without refetching when switching to it
+const TAB_CACHE_TTL: Duration = Duration::from_secs(5);
+/// REST polling interval when the connections websocket is unavailable
+const CONN_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
-#[derive(Debug, Deserialize, Clone)]
-struct Proxy {
-    name: String,
-    #[serde(rename = "type")]
-    proxy_type: String,
-    #[serde(default)]
-    all: Vec<String>,
-    #[serde(default)]
-    now: Option<String>,
-    #[serde(default)]
-    history: Vec<HistoryItem>,
+/// User configuration, read from `<config dir>/clashtui/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    /// Test a group's nodes automatically when focusing its proxy list
+    auto_test: bool,
+    /// File containing the API secret
+    secret_file: Option<PathBuf>,
+    /// Delay test URL per group name, overriding the default
+    test_urls: HashMap<String, String>,
+    /// Initial width of the groups pane in percent
+    group_pane_pct: Option<u16>,
+    /// Disable all mutating actions
+    read_only: bool,
+    /// Show up/down speed gauges below the tabs
+    traffic_gauges: bool,
+    /// Show speeds in bits per second instead of bytes
+    speed_in_bits: bool,
+    /// Tighter list rows and fewer borders to fit more on screen
+    dense: bool,
+    /// Full scale of the traffic gauges, in megabits per second
+    max_bandwidth_mbps: Option<u64>,
+    /// Number of traffic samples to retain
+    history_samples: Option<usize>,
+    /// Show a moving average of recent samples instead of the latest one
+    smoothing: bool,
+    /// Close all connections after a mode switch so new routing applies
+    reset_conns_on_mode_switch: bool,
+    /// Do not capture the mouse
+    no_mouse: bool,
+    /// Enabled tabs in display order; empty means all
+    tabs: Vec<Tab>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct HistoryItem {
-    delay: i64,
+impl Config {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("clashtui").join("config.toml"))
+    }
+
+    fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config {}", path.display()))
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct RulesResponse {
-    rules: Vec<Rule>,
+/// A bookmarked node within a group
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Favorite {
+    group: String,
+    name: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct Rule {
-    #[serde(rename = "type")]
-    rule_type: String,
-    payload: String,
-    proxy: String,
+/// Persistent runtime state, kept in `<data dir>/clashtui/state.json`
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct State {
+    /// Unix timestamp of the last delay test per group
+    tested_groups: HashMap<String, u64>,
+    /// Bookmarked nodes, in the order they were added
+    favorites: Vec<Favorite>,
+    /// Display preferences changed in the UI; override the config defaults
+    group_pane_pct: Option<u16>,
+    speed_in_bits: Option<bool>,
+    dense: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
-struct VersionResponse {
-    version: String,
+impl State {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("clashtui").join("state.json"))
+    }
+
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(content) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(path, content);
+        }
+    }
+
+    fn is_favorite(&self, group: &str, name: &str) -> bool {
+        self.favorites
+            .iter()
+            .any(|f| f.group == group && f.name == name)
+    }
+
+    fn recently_tested(&self, group: &str) -> bool {
+        self.tested_groups
+            .get(group)
+            .is_some_and(|t| unix_now().saturating_sub(*t) < AUTO_TEST_TTL_SECS)
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct DelayResponse {
-    delay: i64,
+/// Resolve the API secret: `--secret`, then `--secret-file`, then the
+/// `secret_file` config key.
+fn resolve_secret(cli: &Cli, config: &Config) -> Result<Option<String>> {
+    if let Some(ref secret) = cli.secret {
+        return Ok(Some(secret.clone()));
+    }
+    let Some(path) = cli.secret_file.as_ref().or(config.secret_file.as_ref()) else {
+        return Ok(None);
+    };
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+    let secret = content.trim();
+    Ok((!secret.is_empty()).then(|| secret.to_string()))
 }
 
-#[derive(Debug, Serialize)]
-struct SelectProxyRequest {
-    name: String,
+// ============================================================================
+// Activity Log
+// ============================================================================
+
+/// Appends timestamped messages to a log file; a no-op when disabled
+#[derive(Clone, Default)]
+struct Logger {
+    file: Option<Arc<Mutex<fs::File>>>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
-struct Traffic {
-    up: u64,
-    down: u64,
+impl Logger {
+    fn open(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        Ok(Self {
+            file: Some(Arc::new(Mutex::new(file))),
+        })
+    }
+
+    fn log(&self, msg: &str) {
+        if let Some(ref file) = self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(file, "{} {}", format_timestamp(unix_now()), msg);
+                let _ = file.flush();
+            }
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct ConnectionsResponse {
-    downloadTotal: u64,
-    uploadTotal: u64,
-    connections: Vec<Connection>,
+/// Format a Unix timestamp as an RFC 3339 UTC string
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct Connection {
-    id: String,
-    metadata: ConnectionMetadata,
-    upload: u64,
-    download: u64,
-    start: String,
-    chains: Vec<String>,
-    rule: String,
+/// Append aggregate connection stats to `path` every `interval` in a
+/// background thread. Controller errors skip the sample.
+fn spawn_stats_logger(client: ClashClient, path: &Path, interval: Duration) -> Result<()> {
+    let jsonl = path.extension().is_some_and(|ext| ext == "jsonl");
+    let is_new = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open stats file {}", path.display()))?;
+    if is_new && !jsonl {
+        writeln!(file, "timestamp,upload_total,download_total,connections")?;
+    }
+
+    thread::spawn(move || loop {
+        if let Ok(resp) = client.get_connections() {
+            let timestamp = format_timestamp(unix_now());
+            let line = if jsonl {
+                serde_json::json!({
+                    "timestamp": timestamp,
+                    "upload_total": resp.upload_total,
+                    "download_total": resp.download_total,
+                    "connections": resp.connections.len(),
+                })
+                .to_string()
+            } else {
+                format!(
+                    "{},{},{},{}",
+                    timestamp,
+                    resp.upload_total,
+                    resp.download_total,
+                    resp.connections.len()
+                )
+            };
+            let _ = writeln!(file, "{}", line);
+            let _ = file.flush();
+        }
+        thread::sleep(interval);
+    });
+    Ok(())
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct ConnectionMetadata {
-    network: String,
-    #[serde(rename = "type")]
-    conn_type: String,
-    #[serde(rename = "sourceIP")]
-    source_ip: String,
-    #[serde(rename = "destinationIP")]
-    destination_ip: String,
-    #[serde(rename = "sourcePort")]
-    source_port: String,
-    #[serde(rename = "destinationPort")]
-    destination_port: String,
-    host: String,
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ConfigResponse {
+/// Output of the `status` command
+#[derive(Debug, Serialize)]
+struct StatusSummary {
     mode: String,
-    #[serde(rename = "port")]
     http_port: u16,
-    #[serde(rename = "socks-port")]
     socks_port: u16,
+    connections: Option<usize>,
+    upload_total: Option<u64>,
+    download_total: Option<u64>,
+    groups: usize,
 }
 
-#[derive(Debug, Serialize)]
-struct UpdateConfigRequest {
-    mode: String,
+// ============================================================================
+// Metrics Export
+// ============================================================================
+
+/// Escape a Prometheus label value
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Current metrics in the Prometheus text exposition format
+fn render_metrics(client: &ClashClient, traffic: &Traffic) -> Result<String> {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, u64)>| {
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n",
+            name, help, name, kind
+        ));
+        for (labels, value) in samples {
+            out.push_str(&format!("{}{} {}\n", name, labels, value));
+        }
+    };
+
+    let conns = client.get_connections()?;
+    metric(
+        "clash_upload_bytes_total",
+        "counter",
+        "Bytes uploaded since the core started",
+        vec![(String::new(), conns.upload_total)],
+    );
+    metric(
+        "clash_download_bytes_total",
+        "counter",
+        "Bytes downloaded since the core started",
+        vec![(String::new(), conns.download_total)],
+    );
+    metric(
+        "clash_connections",
+        "gauge",
+        "Active connections",
+        vec![(String::new(), conns.connections.len() as u64)],
+    );
+    metric(
+        "clash_upload_speed_bytes",
+        "gauge",
+        "Current upload speed in bytes per second",
+        vec![(String::new(), traffic.up)],
+    );
+    metric(
+        "clash_download_speed_bytes",
+        "gauge",
+        "Current download speed in bytes per second",
+        vec![(String::new(), traffic.down)],
+    );
+
+    let mode = client.get_config()?.mode.to_lowercase();
+    metric(
+        "clash_mode",
+        "gauge",
+        "Routing mode, 1 for the active one",
+        ["rule", "global", "direct"]
+            .iter()
+            .map(|m| (format!("{{mode=\"{}\"}}", m), u64::from(mode == *m)))
+            .collect(),
+    );
+
+    let proxies = client.get_proxies()?.proxies;
+    metric(
+        "clash_group_selected",
+        "gauge",
+        "Node currently used by each proxy group",
+        ordered_groups(&proxies)
+            .iter()
+            .filter_map(|group| {
+                let now = proxies.get(group)?.now.as_ref()?;
+                Some((
+                    format!(
+                        "{{group=\"{}\",node=\"{}\"}}",
+                        label_value(group),
+                        label_value(now)
+                    ),
+                    1,
+                ))
+            })
+            .collect(),
+    );
+    Ok(out)
 }
 
-#[derive(Debug, Deserialize)]
-struct ProvidersResponse {
-    providers: HashMap<String, serde_json::Value>,
+/// Answer every HTTP request on `addr` with freshly fetched metrics on
+/// `/metrics` and 404 elsewhere. Requests are served one at a time.
+fn serve_metrics(
+    client: &ClashClient,
+    traffic_rx: &crossbeam_channel::Receiver<Traffic>,
+    addr: &str,
+) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?;
+    println!("Serving metrics on http://{}/metrics", addr);
+
+    let mut traffic = Traffic::default();
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+        // Only the request line matters; headers and body are ignored
+        let mut request_line = String::new();
+        if io::BufReader::new(&stream)
+            .read_line(&mut request_line)
+            .is_err()
+        {
+            continue;
+        }
+        while let Ok(sample) = traffic_rx.try_recv() {
+            traffic = sample;
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let (status, body) = if path != "/metrics" {
+            (
+                "404 Not Found",
+                "Not found; metrics are at /metrics\n".to_string(),
+            )
+        } else {
+            match render_metrics(client, &traffic) {
+                Ok(body) => ("200 OK", body),
+                Err(e) => ("503 Service Unavailable", format!("{:#}\n", e)),
+            }
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+    }
+    Ok(())
 }
 
 // ============================================================================
-// Clash API Client
+// TUI Application
 // ============================================================================
 
-struct ClashClient {
-    base_url: String,
-    secret: Option<String>,
-    client: reqwest::blocking::Client,
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Tab {
+    Proxies,
+    Rules,
+    Conns,
+    Providers,
 }
 
-impl ClashClient {
-    fn new(controller: &str, secret: Option<String>) -> Self {
-        let base_url = if controller.starts_with("http") {
-            controller.to_string()
-        } else {
-            format!("http://{}", controller)
-        };
+impl Tab {
+    const ALL: [Tab; 4] = [Tab::Proxies, Tab::Rules, Tab::Conns, Tab::Providers];
 
-        Self {
-            base_url,
-            secret,
-            client: reqwest::blocking::Client::new(),
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Proxies => "Proxies",
+            Tab::Rules => "Rules",
+            Tab::Conns => "Conns",
+            Tab::Providers => "Providers",
         }
     }
+}
 
-    fn request(&self, method: reqwest::Method, endpoint: &str) -> reqwest::blocking::RequestBuilder {
-        let url = format!("{}{}", self.base_url, endpoint);
-        let mut req = self.client.request(method, &url);
-        if let Some(ref secret) = self.secret {
-            req = req.header("Authorization", format!("Bearer {}", secret));
+/// Configured tab order without duplicates, falling back to all tabs
+fn enabled_tabs(configured: &[Tab]) -> Vec<Tab> {
+    let mut tabs: Vec<Tab> = Vec::new();
+    for tab in configured {
+        if !tabs.contains(tab) {
+            tabs.push(*tab);
         }
-        req
     }
-
-    fn get_proxies(&self) -> Result<ProxiesResponse> {
-        let resp = self
-            .request(reqwest::Method::GET, "/proxies")
-            .send()
-            .context("Failed to fetch proxies")?
-            .json::<ProxiesResponse>()
-            .context("Failed to parse proxies response")?;
-        Ok(resp)
+    if tabs.is_empty() {
+        tabs = Tab::ALL.to_vec();
     }
+    tabs
+}
 
-    fn get_rules(&self) -> Result<RulesResponse> {
-        let resp = self
-            .request(reqwest::Method::GET, "/rules")
-            .send()
-            .context("Failed to fetch rules")?
-            .json::<RulesResponse>()
-            .context("Failed to parse rules response")?;
-        Ok(resp)
-    }
+/// Messages sent from background tasks to the UI
+enum TaskEvent {
+    /// Progress update for the status bar
+    Status(String),
+    /// Task finished; carries the final status message
+    Done(String),
+    /// Background refresh of a tab's data, started at the given instant
+    Fetched(Tab, Instant, Result<TabData>),
+    /// Delay test of a group finished: group, delay per node, final status
+    Tested(String, HashMap<String, i64>, String),
+}
 
-    fn get_version(&self) -> Result<VersionResponse> {
-        let resp = self
-            .request(reqwest::Method::GET, "/version")
-            .send()
-            .context("Failed to fetch version")?
-            .json::<VersionResponse>()
-            .context("Failed to parse version response")?;
-        Ok(resp)
+/// Test the delay of each node from a pool of workers, so a slow node only
+/// holds up its own worker. `progress` gets the number of finished tests.
+fn test_nodes(
+    client: &ClashClient,
+    names: &[String],
+    url: &str,
+    progress: impl Fn(usize),
+) -> HashMap<String, i64> {
+    let (job_tx, job_rx) = crossbeam_channel::unbounded();
+    for name in names {
+        let _ = job_tx.send(name.as_str());
     }
+    drop(job_tx);
 
-    fn select_proxy(&self, group: &str, proxy: &str) -> Result<()> {
-        let endpoint = format!("/proxies/{}", urlencoding::encode(group));
-        self.request(reqwest::Method::PUT, &endpoint)
-            .json(&SelectProxyRequest {
-                name: proxy.to_string(),
-            })
-            .send()
-            .context("Failed to select proxy")?;
-        Ok(())
-    }
+    let (result_tx, result_rx) = crossbeam_channel::unbounded();
+    thread::scope(|scope| {
+        for _ in 0..TEST_CONCURRENCY.min(names.len()) {
+            let (job_rx, result_tx) = (job_rx.clone(), result_tx.clone());
+            scope.spawn(move || {
+                for name in job_rx {
+                    let delay = client.test_delay(name, url, TEST_TIMEOUT_MS).unwrap_or(-1);
+                    let _ = result_tx.send((name.to_string(), delay));
+                }
+            });
+        }
+        drop(result_tx);
 
-    fn test_delay(&self, proxy: &str, url: &str, timeout: u64) -> Result<i64> {
-        let endpoint = format!(
-            "/proxies/{}/delay?url={}&timeout={}",
-            urlencoding::encode(proxy),
-            urlencoding::encode(url),
-            timeout
-        );
-        let resp = self
-            .request(reqwest::Method::GET, &endpoint)
-            .send()
-            .context("Failed to test delay")?;
-
-        if resp.status().is_success() {
-            let delay_resp: DelayResponse = resp.json().context("Failed to parse delay response")?;
-            Ok(delay_resp.delay)
-        } else {
-            Ok(-1) // Timeout or error
+        let mut delays = HashMap::new();
+        for (name, delay) in result_rx {
+            delays.insert(name, delay);
+            progress(delays.len());
         }
-    }
+        delays
+    })
+}
 
-    fn get_config(&self) -> Result<ConfigResponse> {
-        let resp = self
-            .request(reqwest::Method::GET, "/configs")
-            .send()
-            .context("Failed to fetch config")?
-            .json::<ConfigResponse>()
-            .context("Failed to parse config response")?;
-        Ok(resp)
-    }
+/// Data backing one tab, as fetched from the controller
+enum TabData {
+    Proxies(ProxiesResponse, Option<ProxyProvidersResponse>),
+    Rules(RulesResponse),
+    Conns(ConnectionsResponse),
+    Providers(ProxyProvidersResponse),
+}
 
-    fn get_connections(&self) -> Result<ConnectionsResponse> {
-        let resp = self
-            .request(reqwest::Method::GET, "/connections")
-            .send()
-            .context("Failed to fetch connections")?
-            .json::<ConnectionsResponse>()
-            .context("Failed to parse connections response")?;
-        Ok(resp)
-    }
+fn fetch_tab(client: &ClashClient, tab: Tab) -> Result<TabData> {
+    Ok(match tab {
+        // Provider data is only used to disambiguate delays
+        Tab::Proxies => TabData::Proxies(client.get_proxies()?, client.get_proxy_providers().ok()),
+        Tab::Rules => TabData::Rules(client.get_rules()?),
+        Tab::Conns => TabData::Conns(client.get_connections()?),
+        Tab::Providers => TabData::Providers(client.get_proxy_providers()?),
+    })
+}
 
-    fn update_mode(&self, mode: &str) -> Result<()> {
-        let req = UpdateConfigRequest {
-            mode: mode.to_string(),
-        };
-        self.request(reqwest::Method::PATCH, "/configs")
-            .json(&req)
-            .send()
-            .context("Failed to update config")?;
-        Ok(())
-    }
-
-    fn get_proxy_provider_names(&self) -> Result<Vec<String>> {
-        let resp = self
-            .request(reqwest::Method::GET, "/providers/proxies")
-            .send()
-            .context("Failed to fetch proxy providers")?
-            .json::<ProvidersResponse>()
-            .context("Failed to parse proxy providers response")?;
-
-        let mut names: Vec<String> = resp.providers.keys().cloned().collect();
-        names.sort();
-        Ok(names)
-    }
-
-    fn get_rule_provider_names(&self) -> Result<Vec<String>> {
-        let resp = self
-            .request(reqwest::Method::GET, "/providers/rules")
-            .send()
-            .context("Failed to fetch rule providers")?
-            .json::<ProvidersResponse>()
-            .context("Failed to parse rule providers response")?;
-
-        let mut names: Vec<String> = resp.providers.keys().cloned().collect();
-        names.sort();
-        Ok(names)
-    }
-
-    fn update_proxy_provider(&self, provider_name: &str) -> Result<()> {
-        let endpoint = format!("/providers/proxies/{}", urlencoding::encode(provider_name));
-        self.request(reqwest::Method::PUT, &endpoint)
-            .send()
-            .with_context(|| format!("Failed to update proxy provider {}", provider_name))?;
-        Ok(())
-    }
-
-    fn update_rule_provider(&self, provider_name: &str) -> Result<()> {
-        let endpoint = format!("/providers/rules/{}", urlencoding::encode(provider_name));
-        self.request(reqwest::Method::PUT, &endpoint)
-            .send()
-            .with_context(|| format!("Failed to update rule provider {}", provider_name))?;
-        Ok(())
-    }
+/// Which connection field the Conns filter matches against
+#[derive(PartialEq, Clone, Copy)]
+enum ConnFilterMode {
+    Host,
+    Chain,
+    Rule,
+    Source,
 }
 
-// ============================================================================
-// TUI Application
-// ============================================================================
+impl ConnFilterMode {
+    fn next(self) -> Self {
+        match self {
+            ConnFilterMode::Host => ConnFilterMode::Chain,
+            ConnFilterMode::Chain => ConnFilterMode::Rule,
+            ConnFilterMode::Rule => ConnFilterMode::Source,
+            ConnFilterMode::Source => ConnFilterMode::Host,
+        }
+    }
 
-#[derive(PartialEq, Clone, Copy)]
-enum Tab {
-    Proxies,
-    Rules,
-    Conns,
+    fn label(self) -> &'static str {
+        match self {
+            ConnFilterMode::Host => "host",
+            ConnFilterMode::Chain => "chain",
+            ConnFilterMode::Rule => "rule",
+            ConnFilterMode::Source => "source",
+        }
+    }
 }
 
 struct App {
     client: ClashClient,
     current_tab: Tab,
+    // Enabled tabs in display order; drives hotkeys and cycling
+    tabs: Vec<Tab>,
     // Proxy groups
     groups: Vec<String>,
     group_state: ListState,
@@ -358,47 +683,211 @@ struct App {
     proxy_state: ListState,
     // Current group info
     current_group: Option<Proxy>,
+    // Full /proxies map from the last fetch
+    all_proxies: IndexMap<String, Proxy>,
+    // Proxy filters: name/protocol search and protocol type
+    proxy_filter: String,
+    proxy_type_filter: Option<String>,
     // Rules
     rules: Vec<Rule>,
     rule_state: ListState,
     // Connections
     conns: Vec<Connection>,
     conn_state: ListState,
+    // Proxy providers and their nodes
+    providers: Vec<ProxyProvider>,
+    ambiguous_names: HashSet<String>,
+    provider_state: ListState,
+    provider_node_state: ListState,
+    // Connection filter
+    conn_filter: String,
+    conn_filter_mode: ConnFilterMode,
+    // Filter input is being edited
+    filter_editing: bool,
     // Status message
     status: String,
     // Traffic
     traffic: Traffic,
     traffic_rx: crossbeam_channel::Receiver<Traffic>,
+    // Live connection snapshots from the streaming thread
+    conns_rx: Option<crossbeam_channel::Receiver<ConnectionsResponse>>,
+    // Whether the Conns tab is shown; the REST fallback only polls then
+    conns_visible: Arc<AtomicBool>,
+    // Recent traffic samples, newest last
+    traffic_history: VecDeque<Traffic>,
+    history_samples: usize,
+    smoothing: bool,
     // Current Clash Mode
     mode: String,
+    // Mode to restore when leaving Direct via the quick toggle
+    previous_mode: Option<String>,
     // Show help popup
     show_help: bool,
+    // Help page: 0 = keys, 1 = color legend
+    help_page: usize,
+    // Core config popup
+    core_config: Option<ConfigResponse>,
+    // Connection IDs awaiting confirmation to be closed
+    pending_close: Option<Vec<String>>,
+    // Group, node and time of a first Enter in an auto group
+    pending_select: Option<(String, String, Instant)>,
+    // Per-source traffic summary popup
+    show_source_summary: bool,
+    // Favorites popup
+    show_favorites: bool,
+    favorite_state: ListState,
     // Focus: 0 = groups, 1 = proxies
     focus: usize,
+    // Auto-test groups without recent delay data on focus
+    auto_test: bool,
+    // Per-group delay test URLs
+    test_urls: HashMap<String, String>,
+    // Width of the groups pane in percent
+    group_pane_pct: u16,
+    // Mutating actions are disabled
+    read_only: bool,
+    // Close all connections after switching mode
+    reset_conns_on_mode_switch: bool,
+    // Traffic gauges and their full scale in bytes per second
+    traffic_gauges: bool,
+    max_bandwidth: u64,
+    // Show speeds in bits per second
+    speed_in_bits: bool,
+    // Color delays relative to the group's best and worst instead of fixed thresholds
+    relative_delays: bool,
+    // Compact rendering
+    dense: bool,
+    // Persisted state (delay test cache)
+    state: State,
+    // Activity log
+    logger: Logger,
+    // Background task channel
+    task_tx: crossbeam_channel::Sender<TaskEvent>,
+    task_rx: crossbeam_channel::Receiver<TaskEvent>,
+    task_running: bool,
+    // Spinner animation frame while a task runs
+    spinner_frame: usize,
+    // Features supported by the connected core
+    capabilities: Capabilities,
+    // When each tab's data was last fetched successfully
+    last_fetch: HashMap<Tab, Instant>,
+    // Tabs with a background refresh in flight
+    fetching: HashSet<Tab>,
+    // Last seen `now` of each auto-selecting group
+    previous_now: HashMap<String, String>,
 }
 
 impl App {
-    fn new(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>) -> Self {
+    fn new(
+        client: ClashClient,
+        traffic_rx: crossbeam_channel::Receiver<Traffic>,
+        config: Config,
+        logger: Logger,
+    ) -> Self {
+        let (task_tx, task_rx) = crossbeam_channel::unbounded();
+        let tabs = enabled_tabs(&config.tabs);
+        let state = State::load();
         let mut app = Self {
             client,
-            current_tab: Tab::Proxies,
+            current_tab: tabs[0],
+            tabs,
             groups: Vec::new(),
             group_state: ListState::default(),
             proxies: Vec::new(),
             proxy_state: ListState::default(),
             current_group: None,
+            all_proxies: IndexMap::new(),
+            proxy_filter: String::new(),
+            proxy_type_filter: None,
             rules: Vec::new(),
             rule_state: ListState::default(),
             conns: Vec::new(),
             conn_state: ListState::default(),
+            providers: Vec::new(),
+            ambiguous_names: HashSet::new(),
+            provider_state: ListState::default(),
+            provider_node_state: ListState::default(),
+            conn_filter: String::new(),
+            conn_filter_mode: ConnFilterMode::Host,
+            filter_editing: false,
             status: String::from("Press ? for help"),
             traffic: Traffic::default(),
             traffic_rx,
+            conns_rx: None,
+            conns_visible: Arc::new(AtomicBool::new(false)),
+            traffic_history: VecDeque::new(),
+            history_samples: config
+                .history_samples
+                .unwrap_or(DEFAULT_HISTORY_SAMPLES)
+                .max(1),
+            smoothing: config.smoothing,
             mode: String::from("Unknown"),
+            previous_mode: None,
             show_help: false,
+            help_page: 0,
+            core_config: None,
+            pending_close: None,
+            pending_select: None,
+            show_source_summary: false,
+            show_favorites: false,
+            favorite_state: ListState::default(),
             focus: 0,
+            auto_test: config.auto_test,
+            test_urls: config.test_urls,
+            group_pane_pct: state
+                .group_pane_pct
+                .or(config.group_pane_pct)
+                .unwrap_or(DEFAULT_GROUP_PANE_PCT)
+                .clamp(MIN_GROUP_PANE_PCT, MAX_GROUP_PANE_PCT),
+            read_only: config.read_only,
+            reset_conns_on_mode_switch: config.reset_conns_on_mode_switch,
+            traffic_gauges: config.traffic_gauges,
+            speed_in_bits: state.speed_in_bits.unwrap_or(config.speed_in_bits),
+            relative_delays: false,
+            dense: state.dense.unwrap_or(config.dense),
+            max_bandwidth: config
+                .max_bandwidth_mbps
+                .unwrap_or(DEFAULT_MAX_BANDWIDTH_MBPS)
+                .max(1)
+                * 1_000_000
+                / 8,
+            state,
+            logger,
+            task_tx,
+            task_rx,
+            task_running: false,
+            spinner_frame: 0,
+            last_fetch: HashMap::new(),
+            fetching: HashSet::new(),
+            previous_now: HashMap::new(),
+            capabilities: Capabilities::default(),
         };
+        let version = app.client.get_version().ok();
+        if let Some(ref version) = version {
+            app.capabilities = Capabilities::detect(version);
+        }
+        if let Some(tab) = app.tabs.iter().copied().find(|t| app.tab_available(*t)) {
+            app.current_tab = tab;
+        }
+        app.conns_visible
+            .store(app.current_tab == Tab::Conns, Ordering::Relaxed);
+        if app.capabilities.connections && app.tabs.contains(&Tab::Conns) {
+            app.conns_rx = Some(spawn_conns_stream(
+                app.client.clone(),
+                app.logger.clone(),
+                app.conns_visible.clone(),
+            ));
+        }
         app.refresh_data();
+
+        let missing = app.capabilities.missing();
+        if let (Some(version), false) = (version, missing.is_empty()) {
+            app.set_status(format!(
+                "Core {} lacks {}; related features are disabled",
+                version.version,
+                missing.join(", ")
+            ));
+        }
         app
     }
 
@@ -408,234 +897,1136 @@ impl App {
             self.mode = config.mode;
         }
 
-        // Process any traffic updates
-        while let Ok(traffic) = self.traffic_rx.try_recv() {
-            self.traffic = traffic;
-        }
-
         match self.current_tab {
             Tab::Proxies => self.refresh_proxies(),
             Tab::Rules => self.refresh_rules(),
             Tab::Conns => self.refresh_conns(),
+            Tab::Providers => self.refresh_providers(),
         }
     }
 
-    fn refresh_proxies(&mut self) {
-        match self.client.get_proxies() {
-            Ok(resp) => {
-                // Filter only Selector type groups
-                self.groups = resp
-                    .proxies
-                    .iter()
-                    .filter(|(_, p)| p.proxy_type == "Selector" || p.proxy_type == "URLTest" || p.proxy_type == "Fallback")
-                    .map(|(name, _)| name.clone())
-                    .collect();
-                self.groups.sort();
-
-                if !self.groups.is_empty() && self.group_state.selected().is_none() {
-                    self.group_state.select(Some(0));
-                }
+    fn set_status(&mut self, msg: impl Into<String>) {
+        self.status = msg.into();
+        self.logger.log(&self.status);
+    }
 
-                self.update_proxies_for_group(&resp.proxies);
-                self.status = format!("Loaded {} groups", self.groups.len());
-            }
-            Err(e) => {
-                self.status = format!("Error: {}", e);
+    /// Apply messages from background tasks; called once per UI loop
+    fn tick(&mut self) {
+        // Process any traffic updates
+        while let Ok(traffic) = self.traffic_rx.try_recv() {
+            if self.traffic_history.len() >= self.history_samples {
+                self.traffic_history.pop_front();
             }
+            self.traffic_history.push_back(traffic.clone());
+            self.traffic = traffic;
         }
-    }
 
-    fn update_proxies_for_group(&mut self, all_proxies: &HashMap<String, Proxy>) {
-        if let Some(idx) = self.group_state.selected() {
-            if let Some(group_name) = self.groups.get(idx) {
-                if let Some(group) = all_proxies.get(group_name) {
-                    self.current_group = Some(group.clone());
-                    self.proxies = group
-                        .all
-                        .iter()
-                        .map(|name| {
-                            let delay = all_proxies
-                                .get(name)
-                                .and_then(|p| p.history.last())
-                                .map(|h| h.delay)
-                                .unwrap_or(-1);
-                            (name.clone(), delay)
-                        })
-                        .collect();
+        // Only the newest connection snapshot matters
+        let latest = self.conns_rx.as_ref().and_then(|rx| rx.try_iter().last());
+        if let Some(resp) = latest {
+            self.set_conns(resp);
+            self.last_fetch.insert(Tab::Conns, Instant::now());
+        }
 
-                    if !self.proxies.is_empty() && self.proxy_state.selected().is_none() {
-                        self.proxy_state.select(Some(0));
+        if self.task_running {
+            self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+        }
+        while let Ok(event) = self.task_rx.try_recv() {
+            match event {
+                TaskEvent::Status(msg) => self.set_status(msg),
+                TaskEvent::Done(msg) => {
+                    self.task_running = false;
+                    self.refresh_data();
+                    self.set_status(msg);
+                }
+                TaskEvent::Fetched(tab, started, result) => {
+                    self.fetching.remove(&tab);
+                    // A synchronous refresh may have landed newer data meanwhile
+                    if self.last_fetch.get(&tab).is_some_and(|t| *t > started) {
+                        continue;
+                    }
+                    match result {
+                        Ok(data) => self.apply_tab_data(data),
+                        Err(e) => self.set_status(format!("Error: {:#}", e)),
+                    }
+                }
+                TaskEvent::Tested(group, delays, msg) => {
+                    self.task_running = false;
+                    // Pick up the node an auto group elected from the results
+                    self.refresh_proxies();
+                    if self.current_group.as_ref().is_some_and(|g| g.name == group) {
+                        for (name, delay) in self.proxies.iter_mut() {
+                            if let Some(d) = delays.get(name) {
+                                *delay = *d;
+                            }
+                        }
                     }
+                    self.state.tested_groups.insert(group, unix_now());
+                    self.state.save();
+                    self.set_status(msg);
                 }
             }
         }
     }
 
-    fn refresh_rules(&mut self) {
-        match self.client.get_rules() {
-            Ok(resp) => {
-                self.rules = resp.rules;
-                if !self.rules.is_empty() && self.rule_state.selected().is_none() {
-                    self.rule_state.select(Some(0));
-                }
-                self.status = format!("Loaded {} rules", self.rules.len());
-            }
-            Err(e) => {
-                self.status = format!("Error: {}", e);
-            }
+    /// Traffic to display: the latest sample, or a moving average when
+    /// smoothing is enabled.
+    fn displayed_traffic(&self) -> Traffic {
+        if !self.smoothing || self.traffic_history.is_empty() {
+            return self.traffic.clone();
+        }
+        let window: Vec<&Traffic> = self
+            .traffic_history
+            .iter()
+            .rev()
+            .take(SMOOTHING_WINDOW)
+            .collect();
+        let n = window.len() as u64;
+        Traffic {
+            up: window.iter().map(|t| t.up).sum::<u64>() / n,
+            down: window.iter().map(|t| t.down).sum::<u64>() / n,
         }
     }
 
-    fn refresh_conns(&mut self) {
-        match self.client.get_connections() {
-            Ok(resp) => {
-                self.conns = resp.connections;
-                // Sort by start time desc
-                self.conns.sort_by(|a, b| b.start.cmp(&a.start));
-                
-                if !self.conns.is_empty() && self.conn_state.selected().is_none() {
-                    self.conn_state.select(Some(0));
-                }
-                self.status = format!("Loaded {} connections. Up: {}, Down: {}", 
-                    self.conns.len(), 
-                    format_bytes(resp.uploadTotal), 
-                    format_bytes(resp.downloadTotal));
-            }
-            Err(e) => {
-                self.status = format!("Error: {}", e);
-            }
+    /// Highest up/down speeds among the retained samples
+    fn peak_traffic(&self) -> Traffic {
+        Traffic {
+            up: self.traffic_history.iter().map(|t| t.up).max().unwrap_or(0),
+            down: self
+                .traffic_history
+                .iter()
+                .map(|t| t.down)
+                .max()
+                .unwrap_or(0),
         }
     }
 
-    fn select_proxy(&mut self) {
-        if let (Some(group), Some(proxy_idx)) = (&self.current_group, self.proxy_state.selected()) {
-            if let Some((proxy_name, _)) = self.proxies.get(proxy_idx) {
-                match self.client.select_proxy(&group.name, proxy_name) {
-                    Ok(_) => {
-                        self.status = format!("Selected: {} -> {}", group.name, proxy_name);
-                        self.refresh_proxies();
-                    }
-                    Err(e) => {
-                        self.status = format!("Error selecting proxy: {}", e);
-                    }
-                }
-            }
+    /// Mark a background task as started; returns what the task thread needs,
+    /// or None if another task is still running.
+    fn start_task(
+        &mut self,
+        status: &str,
+    ) -> Option<(ClashClient, crossbeam_channel::Sender<TaskEvent>)> {
+        if self.task_running {
+            self.set_status("A background task is already running");
+            return None;
         }
+        self.task_running = true;
+        self.set_status(status);
+        Some((self.client.clone(), self.task_tx.clone()))
     }
 
-    fn test_selected_delay(&mut self) {
-        if let Some(proxy_idx) = self.proxy_state.selected() {
-            if let Some((proxy_name, _)) = self.proxies.get(proxy_idx).cloned() {
-                self.status = format!("Testing {}...", proxy_name);
-                match self.client.test_delay(&proxy_name, "http://www.gstatic.com/generate_204", 5000) {
-                    Ok(delay) => {
-                        if delay > 0 {
-                            self.status = format!("{}: {}ms", proxy_name, delay);
-                            // Update the delay in our list
-                            if let Some(p) = self.proxies.get_mut(proxy_idx) {
-                                p.1 = delay;
-                            }
-                        } else {
-                            self.status = format!("{}: timeout", proxy_name);
-                        }
-                    }
-                    Err(e) => {
-                        self.status = format!("Error testing delay: {}", e);
-                    }
-                }
+    /// Fetch a tab's data synchronously
+    fn refresh_tab(&mut self, tab: Tab) {
+        match fetch_tab(&self.client, tab) {
+            Ok(data) => self.apply_tab_data(data),
+            Err(e) => {
+                self.set_status(format!("Error: {:#}", e));
             }
         }
     }
 
-    fn toggle_mode(&mut self) {
-        let new_mode = match self.mode.as_str() {
-            "Rule" => "Global",
-            "Global" => "Direct",
-            _ => "Rule",
-        };
-        
-        match self.client.update_mode(new_mode) {
-            Ok(_) => {
-                self.mode = new_mode.to_string();
-                self.status = format!("Switched to {} mode", new_mode);
+    /// Refetch a tab's data without blocking the UI; the cached data stays
+    /// on screen until the result arrives.
+    fn refresh_tab_in_background(&mut self, tab: Tab) {
+        if !self.fetching.insert(tab) {
+            return;
+        }
+        let client = self.client.clone();
+        let tx = self.task_tx.clone();
+        let started = Instant::now();
+        thread::spawn(move || {
+            let _ = tx.send(TaskEvent::Fetched(tab, started, fetch_tab(&client, tab)));
+        });
+    }
+
+    fn apply_tab_data(&mut self, data: TabData) {
+        let tab = match data {
+            TabData::Proxies(resp, providers) => {
+                self.apply_proxies(resp, providers);
+                Tab::Proxies
             }
-            Err(e) => {
-                self.status = format!("Error switching mode: {}", e);
+            TabData::Rules(resp) => {
+                self.apply_rules(resp);
+                Tab::Rules
+            }
+            TabData::Conns(resp) => {
+                self.apply_conns(resp);
+                Tab::Conns
             }
+            TabData::Providers(resp) => {
+                self.apply_providers(resp);
+                Tab::Providers
+            }
+        };
+        self.last_fetch.insert(tab, Instant::now());
+    }
+
+    fn refresh_proxies(&mut self) {
+        self.refresh_tab(Tab::Proxies);
+    }
+
+    fn apply_proxies(&mut self, resp: ProxiesResponse, providers: Option<ProxyProvidersResponse>) {
+        // Follow the selected group by name; a subscription update may have
+        // removed or reordered groups
+        let previous = self
+            .group_state
+            .selected()
+            .and_then(|i| self.groups.get(i).cloned());
+        self.groups = ordered_groups(&resp.proxies);
+        let selected = reselect_group(
+            &self.groups,
+            previous.as_deref(),
+            self.group_state.selected(),
+        );
+        self.group_state.select(selected);
+
+        self.all_proxies = resp.proxies;
+        if let Some(providers) = providers {
+            self.set_providers(providers);
+        }
+        if selected.is_none() {
+            self.current_group = None;
+            self.proxies.clear();
+            self.proxy_state.select(None);
         }
+        self.update_proxies_for_group();
+        self.set_status(format!("Loaded {} groups", self.groups.len()));
+        let current = selected.and_then(|i| self.groups.get(i));
+        if let Some(previous) = previous.filter(|p| current != Some(p)) {
+            self.set_status(match current {
+                Some(current) => format!("Group {} is gone; selected {}", previous, current),
+                None => format!("Group {} is gone; no groups left", previous),
+            });
+        }
+        self.report_auto_switches();
     }
 
-    fn refresh_subscriptions(&mut self) {
-        let mut updated_count: usize = 0;
-        let mut failed_items: Vec<String> = Vec::new();
-
-        match self.client.get_proxy_provider_names() {
-            Ok(provider_names) => {
-                for provider_name in provider_names {
-                    if let Err(err) = self.client.update_proxy_provider(&provider_name) {
-                        failed_items.push(format!("proxy:{} ({})", provider_name, err));
-                    } else {
-                        updated_count += 1;
-                    }
+    /// Record a node the user picked so the next refresh does not report it
+    /// as an automatic switch
+    fn note_user_selection(&mut self, group: &str, node: &str) {
+        self.previous_now
+            .insert(group.to_string(), node.to_string());
+    }
+
+    /// Report auto groups whose elected node changed since the last refresh
+    fn report_auto_switches(&mut self) {
+        let mut switches = Vec::new();
+        for (name, group) in &self.all_proxies {
+            if group.proxy_type == "Selector" || group.all.is_empty() {
+                continue;
+            }
+            let Some(ref now) = group.now else {
+                continue;
+            };
+            if let Some(before) = self.previous_now.insert(name.clone(), now.clone()) {
+                if before != *now {
+                    switches.push(format!("{}: {} → {}", name, before, now));
                 }
             }
-            Err(err) => failed_items.push(format!("proxy providers list ({})", err)),
         }
+        if !switches.is_empty() {
+            switches.sort();
+            self.set_status(format!("Auto switched: {}", switches.join("; ")));
+        }
+    }
+
+    fn update_proxies_for_group(&mut self) {
+        if let Some(idx) = self.group_state.selected() {
+            if let Some(group_name) = self.groups.get(idx) {
+                if let Some(group) = self.all_proxies.get(group_name) {
+                    self.current_group = Some(group.clone());
+                    self.proxies = group
+                        .all
+                        .iter()
+                        .filter(|name| self.proxy_matches(name))
+                        .map(|name| {
+                            let delay =
+                                node_delay(&self.all_proxies, &self.providers, group_name, name);
+                            (name.clone(), delay)
+                        })
+                        .collect();
 
-        match self.client.get_rule_provider_names() {
-            Ok(provider_names) => {
-                for provider_name in provider_names {
-                    if let Err(err) = self.client.update_rule_provider(&provider_name) {
-                        failed_items.push(format!("rule:{} ({})", provider_name, err));
+                    if self.proxies.is_empty() {
+                        self.proxy_state.select(None);
                     } else {
-                        updated_count += 1;
+                        let i = self.proxy_state.selected().unwrap_or(0);
+                        self.proxy_state.select(Some(i.min(self.proxies.len() - 1)));
                     }
                 }
             }
-            Err(err) => failed_items.push(format!("rule providers list ({})", err)),
         }
+    }
 
-        self.refresh_data();
-        if failed_items.is_empty() {
-            self.status = format!("Subscriptions refreshed: {} provider(s)", updated_count);
-        } else {
-            self.status = format!(
-                "Refreshed {} provider(s), {} error(s)",
-                updated_count,
-                failed_items.len()
-            );
+    fn proxy_type_of(&self, name: &str) -> Option<&str> {
+        self.all_proxies.get(name).map(|p| p.proxy_type.as_str())
+    }
+
+    /// A node matches when it passes the protocol filter and its name or
+    /// protocol matches the search query.
+    fn proxy_matches(&self, name: &str) -> bool {
+        let proxy_type = self.proxy_type_of(name).unwrap_or("");
+        if let Some(ref wanted) = self.proxy_type_filter {
+            if !proxy_type.eq_ignore_ascii_case(wanted) {
+                return false;
+            }
+        }
+        if self.proxy_filter.is_empty() {
+            return true;
         }
+        let query = self.proxy_filter.to_lowercase();
+        name.to_lowercase().contains(&query) || proxy_type.to_lowercase() == query
     }
 
-    #[allow(dead_code)]
-    fn next_tab(&mut self) {
-        self.current_tab = match self.current_tab {
-            Tab::Proxies => Tab::Rules,
-            Tab::Rules => Tab::Conns,
-            Tab::Conns => Tab::Proxies,
+    fn cycle_proxy_type_filter(&mut self) {
+        let mut types: Vec<String> = self
+            .current_group
+            .as_ref()
+            .map(|g| {
+                g.all
+                    .iter()
+                    .filter_map(|name| self.proxy_type_of(name))
+                    .map(|t| t.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        types.sort();
+        types.dedup();
+
+        self.proxy_type_filter = match self.proxy_type_filter {
+            None => types.first().cloned(),
+            Some(ref current) => types
+                .iter()
+                .position(|t| t == current)
+                .and_then(|i| types.get(i + 1))
+                .cloned(),
         };
-        self.refresh_data();
+        self.proxy_state.select(Some(0));
+        self.update_proxies_for_group();
+        self.set_status(match self.proxy_type_filter {
+            Some(ref t) => format!("Showing {} nodes ({})", t, self.proxies.len()),
+            None => "Showing all protocols".to_string(),
+        });
     }
 
-    fn prev_tab(&mut self) {
-        self.current_tab = match self.current_tab {
-            Tab::Proxies => Tab::Conns,
-            Tab::Conns => Tab::Rules,
-            Tab::Rules => Tab::Proxies,
-        };
-        self.refresh_data();
+    fn refresh_providers(&mut self) {
+        self.refresh_tab(Tab::Providers);
     }
 
-    fn move_up(&mut self) {
-        match self.current_tab {
-            Tab::Proxies => {
-                if self.focus == 0 {
+    fn apply_providers(&mut self, resp: ProxyProvidersResponse) {
+        self.set_providers(resp);
+        clamp_selection(&mut self.provider_state, self.providers.len());
+        let nodes = self.selected_provider().map_or(0, |p| p.proxies.len());
+        clamp_selection(&mut self.provider_node_state, nodes);
+        self.set_status(format!("Loaded {} providers", self.providers.len()));
+    }
+
+    fn set_providers(&mut self, resp: ProxyProvidersResponse) {
+        self.providers = resp.providers.into_values().collect();
+        self.providers.sort_by(|a, b| a.name.cmp(&b.name));
+        self.ambiguous_names = ambiguous_node_names(&self.providers);
+    }
+
+    fn selected_provider(&self) -> Option<&ProxyProvider> {
+        self.provider_state
+            .selected()
+            .and_then(|i| self.providers.get(i))
+    }
+
+    fn selected_provider_node(&self) -> Option<&Proxy> {
+        let idx = self.provider_node_state.selected()?;
+        self.selected_provider()?.proxies.get(idx)
+    }
+
+    /// Test the selected provider node and record the result in its history
+    fn test_provider_node(&mut self) {
+        let Some(name) = self.selected_provider_node().map(|p| p.name.clone()) else {
+            return;
+        };
+        self.set_status(format!("Testing {}...", name));
+        match self
+            .client
+            .test_delay(&name, DEFAULT_TEST_URL, TEST_TIMEOUT_MS)
+        {
+            Ok(delay) => {
+                if let (Some(p), Some(n)) = (
+                    self.provider_state.selected(),
+                    self.provider_node_state.selected(),
+                ) {
+                    if let Some(node) = self.providers.get_mut(p).and_then(|p| p.proxies.get_mut(n))
+                    {
+                        node.history.push(HistoryItem { delay });
+                    }
+                }
+                if delay > 0 {
+                    self.set_status(format!("{}: {}ms", name, delay));
+                } else {
+                    self.set_status(format!("{}: timeout", name));
+                }
+            }
+            Err(e) => self.set_status(format!("Error testing delay: {:#}", e)),
+        }
+    }
+
+    fn healthcheck_selected_provider(&mut self) {
+        let Some(name) = self.selected_provider().map(|p| p.name.clone()) else {
+            return;
+        };
+        let Some((client, tx)) = self.start_task(&format!("Health-checking {}...", name)) else {
+            return;
+        };
+        thread::spawn(move || {
+            let msg = match client.healthcheck_proxy_provider(&name) {
+                Ok(_) => format!("Health check finished: {}", name),
+                Err(e) => format!("Error: {:#}", e),
+            };
+            let _ = tx.send(TaskEvent::Done(msg));
+        });
+    }
+
+    fn refresh_rules(&mut self) {
+        self.refresh_tab(Tab::Rules);
+    }
+
+    fn apply_rules(&mut self, resp: RulesResponse) {
+        self.rules = resp.rules;
+        if !self.rules.is_empty() && self.rule_state.selected().is_none() {
+            self.rule_state.select(Some(0));
+        }
+        self.set_status(format!("Loaded {} rules", self.rules.len()));
+    }
+
+    fn refresh_conns(&mut self) {
+        if !self.capabilities.connections {
+            self.set_status("Connections are not supported by this core");
+            return;
+        }
+        self.refresh_tab(Tab::Conns);
+    }
+
+    fn apply_conns(&mut self, resp: ConnectionsResponse) {
+        let (count, up, down) = (
+            resp.connections.len(),
+            resp.upload_total,
+            resp.download_total,
+        );
+        self.set_conns(resp);
+        self.set_status(format!(
+            "Loaded {} connections. Up: {}, Down: {}",
+            count,
+            format_bytes(up),
+            format_bytes(down)
+        ));
+    }
+
+    /// Replace the connection list, keeping the selected connection selected
+    fn set_conns(&mut self, resp: ConnectionsResponse) {
+        let selected = self.selected_conn().map(|c| c.id.clone());
+        self.conns = resp.connections;
+        // Sort by start time desc
+        self.conns.sort_by(|a, b| b.start.cmp(&a.start));
+        if let Some(idx) =
+            selected.and_then(|id| self.filtered_conns().iter().position(|c| c.id == id))
+        {
+            self.conn_state.select(Some(idx));
+        }
+        self.clamp_conn_selection();
+    }
+
+    fn conn_matches(&self, conn: &Connection) -> bool {
+        if self.conn_filter.is_empty() {
+            return true;
+        }
+        let query = self.conn_filter.to_lowercase();
+        match self.conn_filter_mode {
+            ConnFilterMode::Host => {
+                conn.metadata.host.to_lowercase().contains(&query)
+                    || conn.metadata.destination_ip.contains(&query)
+            }
+            ConnFilterMode::Chain => conn
+                .chains
+                .iter()
+                .any(|c| c.to_lowercase().contains(&query)),
+            ConnFilterMode::Rule => conn.rule_label().to_lowercase().contains(&query),
+            ConnFilterMode::Source => conn.metadata.source_ip.contains(&query),
+        }
+    }
+
+    /// Connection count and traffic totals per source IP over the filtered
+    /// view, busiest first.
+    fn source_summary(&self) -> Vec<(String, usize, u64, u64)> {
+        let mut totals: HashMap<&str, (usize, u64, u64)> = HashMap::new();
+        for conn in self.filtered_conns() {
+            let entry = totals.entry(conn.metadata.source_ip.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 += conn.upload;
+            entry.2 += conn.download;
+        }
+        let mut summary: Vec<(String, usize, u64, u64)> = totals
+            .into_iter()
+            .map(|(ip, (count, up, down))| (ip.to_string(), count, up, down))
+            .collect();
+        summary.sort_by(|a, b| (b.2 + b.3).cmp(&(a.2 + a.3)).then_with(|| a.0.cmp(&b.0)));
+        summary
+    }
+
+    fn filtered_conns(&self) -> Vec<&Connection> {
+        self.conns.iter().filter(|c| self.conn_matches(c)).collect()
+    }
+
+    fn selected_conn(&self) -> Option<&Connection> {
+        let idx = self.conn_state.selected()?;
+        self.filtered_conns().get(idx).copied()
+    }
+
+    fn clamp_conn_selection(&mut self) {
+        let len = self.filtered_conns().len();
+        if len == 0 {
+            self.conn_state.select(None);
+        } else {
+            let i = self.conn_state.selected().unwrap_or(0);
+            self.conn_state.select(Some(i.min(len - 1)));
+        }
+    }
+
+    fn start_filter(&mut self) {
+        match self.current_tab {
+            Tab::Proxies => {
+                self.focus = 1;
+                self.filter_editing = true;
+            }
+            Tab::Conns => self.filter_editing = true,
+            Tab::Rules | Tab::Providers => {}
+        }
+    }
+
+    fn filter_buffer_mut(&mut self) -> Option<&mut String> {
+        match self.current_tab {
+            Tab::Proxies => Some(&mut self.proxy_filter),
+            Tab::Conns => Some(&mut self.conn_filter),
+            Tab::Rules | Tab::Providers => None,
+        }
+    }
+
+    fn cycle_conn_filter_mode(&mut self) {
+        self.conn_filter_mode = self.conn_filter_mode.next();
+        self.clamp_conn_selection();
+        self.set_status(format!(
+            "Filter connections by {}",
+            self.conn_filter_mode.label()
+        ));
+    }
+
+    fn handle_filter_key(&mut self, code: KeyCode) {
+        let Some(buffer) = self.filter_buffer_mut() else {
+            self.filter_editing = false;
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                buffer.clear();
+                self.filter_editing = false;
+            }
+            KeyCode::Enter => {
+                self.filter_editing = false;
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) => {
+                buffer.push(c);
+            }
+            _ => return,
+        }
+        match self.current_tab {
+            Tab::Proxies => {
+                self.proxy_state.select(Some(0));
+                self.update_proxies_for_group();
+            }
+            Tab::Conns => {
+                self.conn_state.select(Some(0));
+                self.clamp_conn_selection();
+            }
+            Tab::Rules | Tab::Providers => {}
+        }
+    }
+
+    /// Returns false (with a status hint) when mutating actions are disabled
+    fn ensure_writable(&mut self) -> bool {
+        if self.read_only {
+            self.set_status("read-only mode: action disabled");
+        }
+        !self.read_only
+    }
+
+    /// Auto groups re-elect on their own: warn and require the same pick a
+    /// second time within `SELECT_CONFIRM_WINDOW` before sending anything.
+    /// Returns true when the selection may be sent.
+    fn confirm_select(&mut self, group: &str, node: &str) -> bool {
+        let Some(group_type) = self
+            .all_proxies
+            .get(group)
+            .map(|g| g.proxy_type.clone())
+            .filter(|t| t != "Selector")
+        else {
+            return true;
+        };
+        let confirmed = self.pending_select.take().is_some_and(|(g, p, at)| {
+            g == group && p == node && at.elapsed() < SELECT_CONFIRM_WINDOW
+        });
+        if !confirmed {
+            self.set_status(format!(
+                "Warning: {} is a {} group, a manual pick may not stick; press Enter again to force-select {}",
+                group, group_type, node
+            ));
+            self.pending_select = Some((group.to_string(), node.to_string(), Instant::now()));
+        }
+        confirmed
+    }
+
+    fn select_proxy(&mut self) {
+        if !self.ensure_writable() {
+            return;
+        }
+        let Some(group) = self.current_group.clone() else {
+            return;
+        };
+        let Some((proxy_name, _)) = self
+            .proxy_state
+            .selected()
+            .and_then(|i| self.proxies.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        if !self.confirm_select(&group.name, &proxy_name) {
+            return;
+        }
+        match self.client.select_proxy(&group.name, &proxy_name) {
+            Ok(_) => {
+                self.note_user_selection(&group.name, &proxy_name);
+                self.refresh_proxies();
+                self.set_status(format!("Selected: {} -> {}", group.name, proxy_name));
+            }
+            Err(e) => {
+                self.set_status(format!("Error selecting proxy: {:#}", e));
+            }
+        }
+    }
+
+    /// Delay test URL for a group, falling back to the default
+    fn test_url_for(&self, group: &str) -> &str {
+        self.test_urls
+            .get(group)
+            .map(|url| url.as_str())
+            .unwrap_or(DEFAULT_TEST_URL)
+    }
+
+    fn current_test_url(&self) -> &str {
+        match self.current_group {
+            Some(ref group) => self.test_url_for(&group.name),
+            None => DEFAULT_TEST_URL,
+        }
+    }
+
+    fn test_selected_delay(&mut self) {
+        if let Some(proxy_idx) = self.proxy_state.selected() {
+            if let Some((proxy_name, _)) = self.proxies.get(proxy_idx).cloned() {
+                self.set_status(format!("Testing {}...", proxy_name));
+                let url = self.current_test_url().to_string();
+                match self.client.test_delay(&proxy_name, &url, TEST_TIMEOUT_MS) {
+                    Ok(delay) => {
+                        if delay > 0 {
+                            self.set_status(format!("{}: {}ms", proxy_name, delay));
+                            // Update the delay in our list
+                            if let Some(p) = self.proxies.get_mut(proxy_idx) {
+                                p.1 = delay;
+                            }
+                        } else {
+                            self.set_status(format!("{}: timeout", proxy_name));
+                        }
+                    }
+                    Err(e) => {
+                        self.set_status(format!("Error testing delay: {:#}", e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Test every listed node of the current group in the background. With
+    /// a filter active only the visible subset is tested.
+    fn test_group_delay(&mut self) {
+        let Some(group_name) = self.current_group.as_ref().map(|g| g.name.clone()) else {
+            return;
+        };
+        let names: Vec<String> = self.proxies.iter().map(|(name, _)| name.clone()).collect();
+        if names.is_empty() {
+            return;
+        }
+        let scope = if !self.proxy_filter.is_empty() || self.proxy_type_filter.is_some() {
+            "filtered nodes"
+        } else {
+            "nodes"
+        };
+        let url = self.test_url_for(&group_name).to_string();
+        let Some((client, tx)) = self.start_task(&format!("Testing {} {}...", names.len(), scope))
+        else {
+            return;
+        };
+        thread::spawn(move || {
+            let total = names.len();
+            let delays = test_nodes(&client, &names, &url, |done| {
+                let _ = tx.send(TaskEvent::Status(format!(
+                    "Testing {}/{} {}...",
+                    done, total, scope
+                )));
+            });
+            let alive = delays.values().filter(|d| **d > 0).count();
+            let msg = format!(
+                "{}: {}/{} {} reachable",
+                group_name,
+                alive,
+                names.len(),
+                scope
+            );
+            let _ = tx.send(TaskEvent::Tested(group_name, delays, msg));
+        });
+    }
+
+    /// Re-test an auto group (URLTest/Fallback/LoadBalance) in the
+    /// background so the core re-elects its node, then report the result.
+    /// This changes routing, so it is disabled in read-only mode.
+    fn reelect_group(&mut self) {
+        if !self.ensure_writable() {
+            return;
+        }
+        let Some(group) = self.current_group.clone() else {
+            return;
+        };
+        if group.proxy_type == "Selector" {
+            self.set_status(format!(
+                "{} is a Selector group; use T to test its nodes",
+                group.name
+            ));
+            return;
+        }
+
+        let url = self.test_url_for(&group.name).to_string();
+        let group_delay = self.capabilities.group_delay;
+        let Some((client, tx)) = self.start_task(&format!("Re-electing {}...", group.name)) else {
+            return;
+        };
+        thread::spawn(move || {
+            let mut delays = if group_delay {
+                match client.test_group(&group.name, &url, TEST_TIMEOUT_MS) {
+                    Ok(delays) => delays,
+                    Err(e) => {
+                        let _ = tx.send(TaskEvent::Done(format!("Error testing group: {:#}", e)));
+                        return;
+                    }
+                }
+            } else {
+                test_nodes(&client, &group.all, &url, |_| {})
+            };
+            for name in &group.all {
+                delays.entry(name.clone()).or_insert(-1);
+            }
+
+            let now = client
+                .get_proxies()
+                .ok()
+                .and_then(|resp| resp.proxies.get(&group.name).and_then(|g| g.now.clone()))
+                .unwrap_or_else(|| "N/A".to_string());
+            let msg = match group.now {
+                Some(ref before) if *before != now => {
+                    format!("{} re-elected: {} → {}", group.name, before, now)
+                }
+                _ => format!("{} kept: {}", group.name, now),
+            };
+            let _ = tx.send(TaskEvent::Tested(group.name, delays, msg));
+        });
+    }
+
+    /// Find the selectable group that carries a connection and the node it
+    /// uses there, as `(group, node, other matching groups)`. Prefers the
+    /// outermost group in the chain; for a bare node, falls back to the
+    /// first group listing it and counts the other matches.
+    fn locate_chain(&self, chains: &[String]) -> Option<(String, String, usize)> {
+        for (i, element) in chains.iter().enumerate().rev() {
+            if self.groups.contains(element) {
+                let node = match i.checked_sub(1) {
+                    Some(inner) => chains[inner].clone(),
+                    None => self.all_proxies.get(element)?.now.clone()?,
+                };
+                return Some((element.clone(), node, 0));
+            }
+        }
+        let node = chains.first()?;
+        let mut matches = self.groups.iter().filter(|g| {
+            self.all_proxies
+                .get(*g)
+                .is_some_and(|p| p.all.contains(node))
+        });
+        let group = matches.next()?.clone();
+        Some((group, node.clone(), matches.count()))
+    }
+
+    /// Show the group and node carrying the selected connection on the
+    /// Proxies tab.
+    fn jump_to_conn_group(&mut self) {
+        let Some(chains) = self.selected_conn().map(|c| c.chains.clone()) else {
+            return;
+        };
+        if chains.is_empty() {
+            self.set_status("Connection is routed DIRECT");
+            return;
+        }
+        if !self.switch_tab(Tab::Proxies) {
+            return;
+        }
+        let Some((group, node, others)) = self.locate_chain(&chains) else {
+            self.set_status(format!("No group found for {}", chains.join(" ← ")));
+            return;
+        };
+        let Some(group_idx) = self.groups.iter().position(|g| *g == group) else {
+            return;
+        };
+        self.group_state.select(Some(group_idx));
+        self.proxy_state.select(Some(0));
+        self.update_proxies_for_group();
+        self.focus = 1;
+        match self.proxies.iter().position(|(name, _)| *name == node) {
+            Some(i) => self.proxy_state.select(Some(i)),
+            None => {
+                self.set_status(format!("{} → {} (hidden by filter)", group, node));
+                return;
+            }
+        }
+        if others > 0 {
+            self.set_status(format!(
+                "{} → {} (also in {} other groups)",
+                group, node, others
+            ));
+        } else {
+            self.set_status(format!("{} → {}", group, node));
+        }
+    }
+
+    /// Move focus to the proxy list, auto-testing the group if enabled and
+    /// it has no recent delay data.
+    fn focus_proxies(&mut self) {
+        self.focus = 1;
+        if !self.auto_test {
+            return;
+        }
+        let Some(group_name) = self.current_group.as_ref().map(|g| g.name.clone()) else {
+            return;
+        };
+        let has_delays = self.proxies.iter().any(|(_, delay)| *delay > 0);
+        // Stay quiet rather than report a busy task on every focus change
+        if !has_delays && !self.task_running && !self.state.recently_tested(&group_name) {
+            self.test_group_delay();
+        }
+    }
+
+    /// Cycle forward: Rule → Global → Direct → Rule
+    fn toggle_mode(&mut self) {
+        let new_mode = match self.mode.to_lowercase().as_str() {
+            "rule" => "Global",
+            "global" => "Direct",
+            _ => "Rule",
+        };
+        self.switch_mode(new_mode);
+    }
+
+    /// Cycle backward: Rule → Direct → Global → Rule
+    fn toggle_mode_reverse(&mut self) {
+        let new_mode = match self.mode.to_lowercase().as_str() {
+            "rule" => "Direct",
+            "direct" => "Global",
+            _ => "Rule",
+        };
+        self.switch_mode(new_mode);
+    }
+
+    /// Switch mode and report it in the status bar
+    fn switch_mode(&mut self, new_mode: &str) {
+        if let Some(note) = self.set_mode(new_mode) {
+            self.set_status(format!("Switched to {} mode{}", new_mode, note));
+        }
+    }
+
+    /// Toggle between Direct and the mode that was active before it
+    fn toggle_direct(&mut self) {
+        let from = self.mode.clone();
+        if from.eq_ignore_ascii_case("direct") {
+            // Keep the remembered mode until the switch back succeeds
+            let to = self
+                .previous_mode
+                .clone()
+                .unwrap_or_else(|| "Rule".to_string());
+            if let Some(note) = self.set_mode(&to) {
+                self.previous_mode = None;
+                self.set_status(format!("Switched {} → {} mode{}", from, to, note));
+            }
+        } else if let Some(note) = self.set_mode("Direct") {
+            self.set_status(format!(
+                "Switched {} → Direct mode{} (d to restore)",
+                from, note
+            ));
+            self.previous_mode = Some(from);
+        }
+    }
+
+    /// Switch the core's mode, leaving the success status to the caller. On
+    /// success returns a note about the connection reset to append to the
+    /// status, empty when disabled.
+    fn set_mode(&mut self, new_mode: &str) -> Option<String> {
+        if !self.ensure_writable() {
+            return None;
+        }
+        match self.client.update_mode(new_mode) {
+            Ok(_) => {
+                self.mode = new_mode.to_string();
+                let note = if self.reset_conns_on_mode_switch {
+                    let note = match self.client.close_all_connections() {
+                        Ok(_) => ", connections reset".to_string(),
+                        Err(e) => format!(", connection reset failed: {:#}", e),
+                    };
+                    self.refresh_conns();
+                    note
+                } else {
+                    String::new()
+                };
+                Some(note)
+            }
+            Err(e) => {
+                self.set_status(format!("Error switching mode: {:#}", e));
+                None
+            }
+        }
+    }
+
+    /// Update all providers in the background, waiting for each proxy
+    /// provider to confirm before the node list is refreshed.
+    fn refresh_subscriptions(&mut self) {
+        if !self.ensure_writable() {
+            return;
+        }
+        let Some((client, tx)) = self.start_task("Refreshing subscriptions...") else {
+            return;
+        };
+        let rule_providers = self.capabilities.rule_providers;
+
+        thread::spawn(move || {
+            let mut updated_count: usize = 0;
+            let mut pending_items: Vec<String> = Vec::new();
+            let mut failed_items: Vec<String> = Vec::new();
+
+            match client.get_proxy_provider_names() {
+                Ok(provider_names) => {
+                    let total = provider_names.len();
+                    for (idx, provider_name) in provider_names.iter().enumerate() {
+                        let _ = tx.send(TaskEvent::Status(format!(
+                            "Updating proxy provider {}/{}: {}",
+                            idx + 1,
+                            total,
+                            provider_name
+                        )));
+                        match client
+                            .update_proxy_provider_and_wait(provider_name, PROVIDER_UPDATE_TIMEOUT)
+                        {
+                            Ok(true) => updated_count += 1,
+                            Ok(false) => pending_items.push(provider_name.clone()),
+                            Err(err) => {
+                                failed_items.push(format!("proxy:{} ({})", provider_name, err))
+                            }
+                        }
+                    }
+                }
+                Err(err) => failed_items.push(format!("proxy providers list ({})", err)),
+            }
+
+            let rule_provider_names = if rule_providers {
+                let _ = tx.send(TaskEvent::Status("Updating rule providers...".to_string()));
+                client.get_rule_provider_names()
+            } else {
+                Ok(Vec::new())
+            };
+            match rule_provider_names {
+                Ok(provider_names) => {
+                    for provider_name in provider_names {
+                        if let Err(err) = client.update_rule_provider(&provider_name) {
+                            failed_items.push(format!("rule:{} ({})", provider_name, err));
+                        } else {
+                            updated_count += 1;
+                        }
+                    }
+                }
+                Err(err) => failed_items.push(format!("rule providers list ({})", err)),
+            }
+
+            let msg = if failed_items.is_empty() && pending_items.is_empty() {
+                format!("Subscriptions updated: {} provider(s)", updated_count)
+            } else {
+                format!(
+                    "Updated {} provider(s), {} unconfirmed, {} error(s)",
+                    updated_count,
+                    pending_items.len(),
+                    failed_items.len()
+                )
+            };
+            let _ = tx.send(TaskEvent::Done(msg));
+        });
+    }
+
+    fn healthcheck_all_providers(&mut self) {
+        let Some((client, tx)) = self.start_task("Health-checking providers...") else {
+            return;
+        };
+        thread::spawn(move || {
+            let result = client.healthcheck_all_providers(|idx, total, name| {
+                let _ = tx.send(TaskEvent::Status(format!(
+                    "Health check {}/{}: {}",
+                    idx, total, name
+                )));
+            });
+            let msg = match result {
+                Ok((checked, failed)) if failed.is_empty() => {
+                    format!("Health check finished: {} provider(s)", checked)
+                }
+                Ok((checked, failed)) => format!(
+                    "Health-checked {} provider(s), {} error(s)",
+                    checked,
+                    failed.len()
+                ),
+                Err(e) => format!("Error: {:#}", e),
+            };
+            let _ = tx.send(TaskEvent::Done(msg));
+        });
+    }
+
+    /// Ask to close every connection in the filtered view
+    fn confirm_close_filtered(&mut self) {
+        if !self.ensure_writable() {
+            return;
+        }
+        let ids: Vec<String> = self.filtered_conns().iter().map(|c| c.id.clone()).collect();
+        if ids.is_empty() {
+            self.set_status("No connections to close");
+            return;
+        }
+        self.pending_close = Some(ids);
+    }
+
+    fn handle_close_confirm_key(&mut self, code: KeyCode) {
+        let Some(ids) = self.pending_close.take() else {
+            return;
+        };
+        if !matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+            self.set_status("Close cancelled");
+            return;
+        }
+        let Some((client, tx)) = self.start_task("Closing connections...") else {
+            return;
+        };
+        thread::spawn(move || {
+            let total = ids.len();
+            let mut failed = 0;
+            for (idx, id) in ids.iter().enumerate() {
+                let _ = tx.send(TaskEvent::Status(format!(
+                    "Closing connection {}/{}",
+                    idx + 1,
+                    total
+                )));
+                if client.close_connection(id).is_err() {
+                    failed += 1;
+                }
+            }
+            let msg = if failed == 0 {
+                format!("Closed {} connection(s)", total)
+            } else {
+                format!("Closed {} connection(s), {} failed", total - failed, failed)
+            };
+            let _ = tx.send(TaskEvent::Done(msg));
+        });
+    }
+
+    fn tab_available(&self, tab: Tab) -> bool {
+        tab != Tab::Conns || self.capabilities.connections
+    }
+
+    /// Returns false (with a status hint) when the tab cannot be shown
+    fn switch_tab(&mut self, tab: Tab) -> bool {
+        if !self.tabs.contains(&tab) {
+            self.set_status(format!("The {} tab is disabled in the config", tab.title()));
+            return false;
+        }
+        if !self.tab_available(tab) {
+            self.set_status("This tab is not supported by the connected core");
+            return false;
+        }
+        self.current_tab = tab;
+        self.conns_visible
+            .store(tab == Tab::Conns, Ordering::Relaxed);
+        // Show cached data at once; only a tab never loaded blocks on a fetch
+        match self.last_fetch.get(&tab) {
+            None => self.refresh_data(),
+            Some(fetched) if fetched.elapsed() < TAB_CACHE_TTL => {}
+            Some(_) => self.refresh_tab_in_background(tab),
+        }
+        true
+    }
+
+    /// Switch to the `n`th enabled tab, counting from 1
+    fn switch_to_nth_tab(&mut self, n: usize) {
+        if let Some(tab) = n.checked_sub(1).and_then(|i| self.tabs.get(i)) {
+            self.switch_tab(*tab);
+        }
+    }
+
+    /// Step through the enabled tabs, skipping unavailable ones
+    fn cycle_tab(&mut self, forward: bool) {
+        let len = self.tabs.len();
+        let current = self
+            .tabs
+            .iter()
+            .position(|t| *t == self.current_tab)
+            .unwrap_or(0);
+        for step in 1..len {
+            let idx = if forward {
+                (current + step) % len
+            } else {
+                (current + len - step) % len
+            };
+            let tab = self.tabs[idx];
+            if self.tab_available(tab) {
+                self.switch_tab(tab);
+                return;
+            }
+        }
+    }
+
+    fn next_tab(&mut self) {
+        self.cycle_tab(true);
+    }
+
+    fn prev_tab(&mut self) {
+        self.cycle_tab(false);
+    }
+
+    fn move_up(&mut self) {
+        match self.current_tab {
+            Tab::Proxies => {
+                if self.focus == 0 {
                     let i = self.group_state.selected().unwrap_or(0);
                     if i > 0 {
                         self.group_state.select(Some(i - 1));
                         self.proxy_state.select(Some(0));
                         if let Ok(resp) = self.client.get_proxies() {
-                            self.update_proxies_for_group(&resp.proxies);
+                            self.all_proxies = resp.proxies;
+                            self.update_proxies_for_group();
                         }
                     }
                 } else {
@@ -657,6 +2048,20 @@ impl App {
                     self.conn_state.select(Some(i - 1));
                 }
             }
+            Tab::Providers => {
+                if self.focus == 0 {
+                    let i = self.provider_state.selected().unwrap_or(0);
+                    if i > 0 {
+                        self.provider_state.select(Some(i - 1));
+                        self.provider_node_state.select(Some(0));
+                    }
+                } else {
+                    let i = self.provider_node_state.selected().unwrap_or(0);
+                    if i > 0 {
+                        self.provider_node_state.select(Some(i - 1));
+                    }
+                }
+            }
         }
     }
 
@@ -669,7 +2074,8 @@ impl App {
                         self.group_state.select(Some(i + 1));
                         self.proxy_state.select(Some(0));
                         if let Ok(resp) = self.client.get_proxies() {
-                            self.update_proxies_for_group(&resp.proxies);
+                            self.all_proxies = resp.proxies;
+                            self.update_proxies_for_group();
                         }
                     }
                 } else {
@@ -687,18 +2093,208 @@ impl App {
             }
             Tab::Conns => {
                 let i = self.conn_state.selected().unwrap_or(0);
-                if i < self.conns.len().saturating_sub(1) {
+                if i < self.filtered_conns().len().saturating_sub(1) {
                     self.conn_state.select(Some(i + 1));
                 }
             }
+            Tab::Providers => {
+                if self.focus == 0 {
+                    let i = self.provider_state.selected().unwrap_or(0);
+                    if i < self.providers.len().saturating_sub(1) {
+                        self.provider_state.select(Some(i + 1));
+                        self.provider_node_state.select(Some(0));
+                    }
+                } else {
+                    let i = self.provider_node_state.selected().unwrap_or(0);
+                    let len = self.selected_provider().map_or(0, |p| p.proxies.len());
+                    if i < len.saturating_sub(1) {
+                        self.provider_node_state.select(Some(i + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    fn open_core_config(&mut self) {
+        match self.client.get_config() {
+            Ok(config) => {
+                self.mode = config.mode.clone();
+                self.core_config = Some(config);
+            }
+            Err(e) => self.set_status(format!("Error: {:#}", e)),
+        }
+    }
+
+    /// Select the next node (wrapping) whose delay shows as `---`
+    fn jump_to_next_timeout(&mut self) {
+        let failed: Vec<usize> = self
+            .proxies
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, delay))| *delay <= 0)
+            .map(|(i, _)| i)
+            .collect();
+        if failed.is_empty() {
+            self.set_status("No timed-out nodes in this group");
+            return;
         }
+
+        let current = self.proxy_state.selected();
+        let pos = failed.iter().position(|i| Some(*i) > current).unwrap_or(0);
+        let idx = failed[pos];
+        self.focus = 1;
+        self.proxy_state.select(Some(idx));
+        self.set_status(format!(
+            "Timed out {}/{}: {}",
+            pos + 1,
+            failed.len(),
+            self.proxies[idx].0
+        ));
+    }
+
+    fn toggle_favorite(&mut self) {
+        let Some(group_name) = self.current_group.as_ref().map(|g| g.name.clone()) else {
+            return;
+        };
+        let Some((name, _)) = self
+            .proxy_state
+            .selected()
+            .and_then(|i| self.proxies.get(i))
+        else {
+            return;
+        };
+        let name = name.clone();
+
+        if self.state.is_favorite(&group_name, &name) {
+            self.state
+                .favorites
+                .retain(|f| !(f.group == group_name && f.name == name));
+            self.set_status(format!("Removed favorite: {} → {}", group_name, name));
+        } else {
+            self.state.favorites.push(Favorite {
+                group: group_name.clone(),
+                name: name.clone(),
+            });
+            self.set_status(format!("Added favorite: {} → {}", group_name, name));
+        }
+        self.state.save();
+    }
+
+    fn open_favorites(&mut self) {
+        if self.state.favorites.is_empty() {
+            self.set_status("No favorites yet; press * on a node to add one");
+            return;
+        }
+        if self.all_proxies.is_empty() {
+            if let Ok(resp) = self.client.get_proxies() {
+                self.all_proxies = resp.proxies;
+            }
+        }
+        self.favorite_state.select(Some(0));
+        self.show_favorites = true;
+    }
+
+    fn handle_favorites_key(&mut self, code: KeyCode) {
+        let len = self.state.favorites.len();
+        let i = self.favorite_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('F') => self.show_favorites = false,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.favorite_state.select(Some(i.saturating_sub(1)))
+            }
+            KeyCode::Down | KeyCode::Char('j') => self
+                .favorite_state
+                .select(Some((i + 1).min(len.saturating_sub(1)))),
+            KeyCode::Enter => self.select_favorite(i),
+            KeyCode::Char(c @ '1'..='9') => {
+                let idx = c as usize - '1' as usize;
+                if idx < len {
+                    // Keep Enter on the same entry for a confirmation
+                    self.favorite_state.select(Some(idx));
+                }
+                self.select_favorite(idx);
+            }
+            _ => {}
+        }
+    }
+
+    fn select_favorite(&mut self, idx: usize) {
+        let Some(fav) = self.state.favorites.get(idx).cloned() else {
+            return;
+        };
+        if !self.ensure_writable() {
+            self.show_favorites = false;
+            return;
+        }
+        // The popup stays open while an auto group awaits confirmation
+        if !self.confirm_select(&fav.group, &fav.name) {
+            return;
+        }
+        self.show_favorites = false;
+        match self.client.select_proxy(&fav.group, &fav.name) {
+            Ok(_) => {
+                self.note_user_selection(&fav.group, &fav.name);
+                if self.current_tab == Tab::Proxies {
+                    self.refresh_proxies();
+                }
+                self.set_status(format!("Selected: {} -> {}", fav.group, fav.name));
+            }
+            Err(e) => self.set_status(format!("Error selecting proxy: {:#}", e)),
+        }
+    }
+
+    /// Switch speed display between bytes and bits per second and persist it
+    fn toggle_speed_unit(&mut self) {
+        self.speed_in_bits = !self.speed_in_bits;
+        self.state.speed_in_bits = Some(self.speed_in_bits);
+        self.state.save();
+        let unit = if self.speed_in_bits { "bits" } else { "bytes" };
+        self.set_status(format!("Speeds shown in {} per second", unit));
+    }
+
+    fn toggle_dense(&mut self) {
+        self.dense = !self.dense;
+        self.state.dense = Some(self.dense);
+        self.state.save();
+        self.set_status(format!(
+            "Dense mode {}",
+            if self.dense { "on" } else { "off" }
+        ));
+    }
+
+    /// Widen (positive) or narrow (negative) the groups pane and persist it
+    fn resize_group_pane(&mut self, delta: i16) {
+        let pct = self
+            .group_pane_pct
+            .saturating_add_signed(delta)
+            .clamp(MIN_GROUP_PANE_PCT, MAX_GROUP_PANE_PCT);
+        if pct == self.group_pane_pct {
+            return;
+        }
+        self.group_pane_pct = pct;
+        self.state.group_pane_pct = Some(pct);
+        self.state.save();
+        self.set_status(format!("Groups pane: {}%", pct));
     }
 
     fn toggle_focus(&mut self) {
-        if self.current_tab == Tab::Proxies {
-            self.focus = 1 - self.focus;
+        match self.current_tab {
+            Tab::Proxies => {
+                if self.focus == 0 {
+                    self.focus_proxies();
+                } else {
+                    self.focus = 0;
+                }
+            }
+            Tab::Providers => self.focus = 1 - self.focus,
+            Tab::Rules | Tab::Conns => {}
         }
     }
+
+    /// Whether the current tab has a left/right pane split
+    fn has_panes(&self) -> bool {
+        matches!(self.current_tab, Tab::Proxies | Tab::Providers)
+    }
 }
 
 // ============================================================================
@@ -711,24 +2307,43 @@ fn ui(f: &mut Frame, app: &mut App) {
         .constraints([
             Constraint::Length(3), // Tabs
             Constraint::Min(0),    // Main content
+            Constraint::Length(2), // Selection details
             Constraint::Length(4), // Status bar + Hints
         ])
         .split(f.size());
 
     // Tabs
-    let tab_titles = vec!["Proxies [1]", "Rules [2]", "Conns [3]"];
-    let selected_tab = match app.current_tab {
-        Tab::Proxies => 0,
-        Tab::Rules => 1,
-        Tab::Conns => 2,
-    };
-    
+    let tab_titles: Vec<String> = app
+        .tabs
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| format!("{} [{}]", tab.title(), i + 1))
+        .collect();
+    let selected_tab = app
+        .tabs
+        .iter()
+        .position(|t| *t == app.current_tab)
+        .unwrap_or(0);
+
     // Format traffic
-    let up_speed = format_speed(app.traffic.up);
-    let down_speed = format_speed(app.traffic.down);
-    
-    let title = format!(" ClashTUI - Mode: {} | ↑ {} | ↓ {} ", app.mode, up_speed, down_speed);
-    
+    let traffic = app.displayed_traffic();
+    let up_speed = format_speed(traffic.up, app.speed_in_bits);
+    let down_speed = format_speed(traffic.down, app.speed_in_bits);
+
+    let updated = app
+        .last_fetch
+        .get(&app.current_tab)
+        .map(|t| format!(" | updated {} ago", format_age(t.elapsed())))
+        .unwrap_or_default();
+    let title = format!(
+        " ClashTUI{} - Mode: {} | ↑ {} | ↓ {}{} ",
+        if app.read_only { " [RO]" } else { "" },
+        app.mode,
+        up_speed,
+        down_speed,
+        updated
+    );
+
     let tabs = Tabs::new(tab_titles)
         .block(Block::default().borders(Borders::ALL).title(title))
         .select(selected_tab)
@@ -740,17 +2355,36 @@ fn ui(f: &mut Frame, app: &mut App) {
         );
     f.render_widget(tabs, chunks[0]);
 
-    // Main content
+    // Main content, optionally below the traffic gauges
+    let main_area = if app.traffic_gauges {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(chunks[1]);
+        render_traffic_gauges(f, app, rows[0]);
+        rows[1]
+    } else {
+        chunks[1]
+    };
+
     match app.current_tab {
-        Tab::Proxies => render_proxies_tab(f, app, chunks[1]),
-        Tab::Rules => render_rules_tab(f, app, chunks[1]),
-        Tab::Conns => render_conns_tab(f, app, chunks[1]),
+        Tab::Proxies => render_proxies_tab(f, app, main_area),
+        Tab::Rules => render_rules_tab(f, app, main_area),
+        Tab::Conns => render_conns_tab(f, app, main_area),
+        Tab::Providers => render_providers_tab(f, app, main_area),
     }
 
     // Status bar
     let status_text = vec![
         Line::from(vec![
-            Span::styled(" ", Style::default()),
+            Span::styled(
+                if app.task_running {
+                    format!(" {} ", SPINNER_FRAMES[app.spinner_frame])
+                } else {
+                    " ".to_string()
+                },
+                Style::default().fg(Color::Cyan),
+            ),
             Span::styled(&app.status, Style::default().fg(Color::Yellow)),
         ]),
         Line::from(vec![
@@ -773,20 +2407,234 @@ fn ui(f: &mut Frame, app: &mut App) {
         ]),
     ];
 
+    f.render_widget(Paragraph::new(detail_lines(app)), chunks[2]);
+
     let status = Paragraph::new(status_text)
         .block(Block::default().borders(Borders::ALL).title(" Status "));
-    f.render_widget(status, chunks[2]);
+    f.render_widget(status, chunks[3]);
 
     // Help popup
+    if app.show_favorites {
+        render_favorites_popup(f, app);
+    }
+
+    if let Some(ref config) = app.core_config {
+        render_config_popup(f, config);
+    }
+
+    if app.show_source_summary {
+        render_source_summary_popup(f, app);
+    }
+
+    if let Some(ref ids) = app.pending_close {
+        render_close_confirm_popup(f, app, ids.len());
+    }
+
     if app.show_help {
-        render_help_popup(f);
+        render_help_popup(f, app.help_page, &app.tabs);
+    }
+}
+
+fn render_traffic_gauges(f: &mut Frame, app: &App, area: Rect) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let traffic = app.displayed_traffic();
+    let peak = app.peak_traffic();
+    let gauge = |label: &str, speed: u64, peak: u64, color: Color| {
+        Gauge::default()
+            .gauge_style(Style::default().fg(color).bg(Color::Rgb(40, 40, 40)))
+            .ratio((speed as f64 / app.max_bandwidth as f64).min(1.0))
+            .label(format!(
+                "{} {} (peak {})",
+                label,
+                format_speed(speed, app.speed_in_bits),
+                format_speed(peak, app.speed_in_bits)
+            ))
+    };
+
+    f.render_widget(gauge("↑", traffic.up, peak.up, Color::Yellow), halves[0]);
+    f.render_widget(gauge("↓", traffic.down, peak.down, Color::Green), halves[1]);
+}
+
+/// Two lines describing the currently selected item, shown above the status bar
+fn detail_lines(app: &App) -> Vec<Line<'static>> {
+    let label =
+        |text: &str| Span::styled(format!(" {}: ", text), Style::default().fg(Color::DarkGray));
+    let value = |text: String| Span::styled(text, Style::default().fg(Color::White));
+
+    match app.current_tab {
+        Tab::Proxies if app.focus == 0 => {
+            let Some(group) = app.current_group.as_ref() else {
+                return Vec::new();
+            };
+            vec![
+                Line::from(vec![
+                    label("Group"),
+                    value(group.name.clone()),
+                    label("Type"),
+                    value(group.proxy_type.clone()),
+                ]),
+                Line::from(vec![
+                    label("Now"),
+                    value({
+                        let chain = resolve_now_chain(&app.all_proxies, &group.name);
+                        if chain.is_empty() {
+                            "N/A".to_string()
+                        } else {
+                            chain.join(" → ")
+                        }
+                    }),
+                    label("Nodes"),
+                    value(group.all.len().to_string()),
+                ]),
+            ]
+        }
+        Tab::Proxies => {
+            let Some((name, delay)) = app.proxy_state.selected().and_then(|i| app.proxies.get(i))
+            else {
+                return Vec::new();
+            };
+            let proxy = app.all_proxies.get(name);
+            let delay_str = if *delay > 0 {
+                format!("{}ms", delay)
+            } else {
+                "---".to_string()
+            };
+            vec![
+                Line::from(vec![label("Node"), value(name.clone())]),
+                Line::from(vec![
+                    label("Type"),
+                    value(proxy.map(|p| p.proxy_type.clone()).unwrap_or_default()),
+                    label("Delay"),
+                    value(delay_str),
+                    label("UDP"),
+                    value(
+                        if proxy.is_some_and(|p| p.udp) {
+                            "yes"
+                        } else {
+                            "no"
+                        }
+                        .to_string(),
+                    ),
+                ]),
+            ]
+        }
+        Tab::Rules => {
+            let Some((idx, rule)) = app
+                .rule_state
+                .selected()
+                .and_then(|i| app.rules.get(i).map(|r| (i, r)))
+            else {
+                return Vec::new();
+            };
+            vec![
+                Line::from(vec![
+                    label(&format!("Rule #{}", idx + 1)),
+                    value(rule.rule_type.clone()),
+                    label("Target"),
+                    value(rule.proxy.clone()),
+                ]),
+                Line::from(vec![label("Payload"), value(rule.payload.clone())]),
+            ]
+        }
+        Tab::Providers if app.focus == 0 => {
+            let Some(provider) = app.selected_provider() else {
+                return Vec::new();
+            };
+            vec![
+                Line::from(vec![
+                    label("Provider"),
+                    value(provider.name.clone()),
+                    label("Vehicle"),
+                    value(provider.vehicle_type.clone()),
+                ]),
+                Line::from(vec![
+                    label("Updated"),
+                    value(
+                        provider
+                            .updated_at
+                            .clone()
+                            .unwrap_or_else(|| "N/A".to_string()),
+                    ),
+                    label("Nodes"),
+                    value(provider.proxies.len().to_string()),
+                ]),
+            ]
+        }
+        Tab::Providers => {
+            let Some(node) = app.selected_provider_node() else {
+                return Vec::new();
+            };
+            let delay = node.history.last().map(|h| h.delay).unwrap_or(-1);
+            let delay_str = if delay > 0 {
+                format!("{}ms", delay)
+            } else {
+                "---".to_string()
+            };
+            vec![
+                Line::from(vec![label("Node"), value(node.name.clone())]),
+                Line::from(vec![
+                    label("Type"),
+                    value(node.proxy_type.clone()),
+                    label("Delay"),
+                    value(delay_str),
+                    label("Tests"),
+                    value(node.history.len().to_string()),
+                ]),
+            ]
+        }
+        Tab::Conns => {
+            let Some(c) = app.selected_conn() else {
+                return Vec::new();
+            };
+            let host = if c.metadata.host.is_empty() {
+                &c.metadata.destination_ip
+            } else {
+                &c.metadata.host
+            };
+            let chains = if c.chains.is_empty() {
+                "DIRECT".to_string()
+            } else {
+                c.chains.iter().rev().cloned().collect::<Vec<_>>().join(" ← ")
+            };
+            let mut summary = vec![
+                label("Dest"),
+                value(format!("{}:{}", host, c.metadata.destination_port)),
+                label("Source"),
+                value(format!(
+                    "{}:{}",
+                    c.metadata.source_ip, c.metadata.source_port
+                )),
+                label("Type"),
+            ];
+            summary.extend(conn_badges(&c.metadata));
+            summary.push(value(format!(
+                "{} {}",
+                c.metadata.network, c.metadata.conn_type
+            )));
+            vec![
+                Line::from(summary),
+                Line::from(vec![
+                    label("Rule"),
+                    value(c.rule_label()),
+                    label("Chain"),
+                    value(chains),
+                ]),
+            ]
+        }
     }
 }
 
 fn render_proxies_tab(f: &mut Frame, app: &mut App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .constraints([
+            Constraint::Percentage(app.group_pane_pct),
+            Constraint::Percentage(100 - app.group_pane_pct),
+        ])
         .split(area);
 
     // Groups list
@@ -795,14 +2643,18 @@ fn render_proxies_tab(f: &mut Frame, app: &mut App, area: Rect) {
         .iter()
         .map(|name| {
             // 在名称前添加空格，增加 padding
-            let content = Line::from(Span::raw(format!(" {}", name)));
+            let pad = if app.dense { "" } else { " " };
+            let content = Line::from(Span::raw(format!("{}{}", pad, name)));
             ListItem::new(content)
         })
         .collect();
 
     let groups_block = Block::default()
-        .borders(Borders::ALL)
-        .title(" Groups ")
+        .borders(pane_borders(app))
+        .title(format!(
+            " Groups ({}) ",
+            position_label(&app.group_state, app.groups.len())
+        ))
         .border_style(if app.focus == 0 {
             Style::default().fg(Color::Cyan)
         } else {
@@ -820,28 +2672,33 @@ fn render_proxies_tab(f: &mut Frame, app: &mut App, area: Rect) {
         .highlight_symbol("▎"); // 使用竖条作为指示符，更简洁
 
     f.render_stateful_widget(groups, chunks[0], &mut app.group_state);
+    if app.groups.is_empty() {
+        render_placeholder(f, chunks[0], "No proxy groups");
+    }
 
     // Proxies list
     let current_proxy = app.current_group.as_ref().and_then(|g| g.now.clone());
+    let current_group_name = app.current_group.as_ref().map(|g| g.name.as_str());
+    let (name_width, delay_width) = node_column_widths(app);
+    let tested = app.proxies.iter().map(|(_, d)| *d).filter(|d| *d > 0);
+    let delay_range = tested.clone().min().zip(tested.max());
     let proxy_items: Vec<ListItem> = app
         .proxies
         .iter()
         .map(|(name, delay)| {
             let is_selected = current_proxy.as_ref() == Some(name);
+            let supports_udp = app.all_proxies.get(name).is_some_and(|p| p.udp);
+            let is_ambiguous = app.ambiguous_names.contains(name);
+            let is_favorite = current_group_name.is_some_and(|g| app.state.is_favorite(g, name));
             let delay_str = if *delay > 0 {
                 format!("{}ms", delay)
             } else {
                 "---".to_string()
             };
 
-            let delay_color = if *delay < 0 {
-                Color::DarkGray
-            } else if *delay < 200 {
-                Color::Green
-            } else if *delay < 500 {
-                Color::Yellow
-            } else {
-                Color::Red
+            let delay_color = match delay_range {
+                Some((min, max)) if app.relative_delays => relative_delay_color(*delay, min, max),
+                _ => delay_color(*delay),
             };
 
             let marker = if is_selected { "●" } else { " " }; // 移除圆点后的空格，由格式化控制
@@ -854,26 +2711,70 @@ fn render_proxies_tab(f: &mut Frame, app: &mut App, area: Rect) {
                 Style::default()
             };
 
+            let marker = if app.dense {
+                marker.to_string()
+            } else {
+                format!(" {} ", marker)
+            };
             let content = Line::from(vec![
-                Span::styled(format!(" {} ", marker), Style::default().fg(marker_color)),
+                Span::styled(marker, Style::default().fg(marker_color)),
+                Span::styled(
+                    if is_favorite { "★" } else { " " },
+                    Style::default().fg(Color::Yellow),
+                ),
                 Span::styled(
-                    format!("{:<30} ", name), // 增加右侧空格
+                    format!(" {:<w$} ", name, w = name_width), // 增加右侧空格
                     name_style,
                 ),
-                Span::styled(format!("{:>8} ", delay_str), Style::default().fg(delay_color)), // 增加右侧空格
+                Span::styled(
+                    if supports_udp { "U " } else { "  " },
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::styled(
+                    format!("{:>w$} ", delay_str, w = delay_width),
+                    Style::default().fg(delay_color),
+                ), // 增加右侧空格
+                Span::styled(
+                    if is_ambiguous { "?" } else { " " },
+                    Style::default().fg(Color::LightRed),
+                ),
             ]);
             ListItem::new(content)
         })
         .collect();
 
-    let group_title = app
+    let mut group_title = app
         .current_group
         .as_ref()
         .map(|g| format!(" {} ({}) ", g.name, g.proxy_type))
         .unwrap_or_else(|| " Proxies ".to_string());
+    group_title.push_str(&format!(
+        "({}) ",
+        position_label(&app.proxy_state, app.proxies.len())
+    ));
+    if let Some(ref group) = app.current_group {
+        // Show the effective node when the selection is itself a group
+        let chain = resolve_now_chain(&app.all_proxies, &group.name);
+        if chain.len() > 1 {
+            group_title.push_str(&format!("[{}] ", chain.join(" → ")));
+        }
+    }
+    if let Some(ref t) = app.proxy_type_filter {
+        group_title.push_str(&format!("[type: {}] ", t));
+    }
+    if app.relative_delays {
+        group_title.push_str("[relative] ");
+    }
+    if !app.proxy_filter.is_empty() || app.filter_editing {
+        group_title.push_str(&format!(
+            "[/ {}{}] ",
+            app.proxy_filter,
+            if app.filter_editing { "▏" } else { "" }
+        ));
+    }
 
     let proxies_block = Block::default()
-        .borders(Borders::ALL)
+        .borders(pane_borders(app))
         .title(group_title)
         .border_style(if app.focus == 1 {
             Style::default().fg(Color::Cyan)
@@ -891,6 +2792,140 @@ fn render_proxies_tab(f: &mut Frame, app: &mut App, area: Rect) {
         .highlight_symbol("▎");
 
     f.render_stateful_widget(proxies, chunks[1], &mut app.proxy_state);
+    if app.proxies.is_empty() && app.current_group.is_some() {
+        let filtered = !app.proxy_filter.is_empty() || app.proxy_type_filter.is_some();
+        render_placeholder(
+            f,
+            chunks[1],
+            if filtered {
+                "No proxies match the filter"
+            } else {
+                "No proxies in this group"
+            },
+        );
+    }
+}
+
+fn render_providers_tab(f: &mut Frame, app: &mut App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(app.group_pane_pct),
+            Constraint::Percentage(100 - app.group_pane_pct),
+        ])
+        .split(area);
+
+    let focused = |pane: usize| {
+        if app.focus == pane {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        }
+    };
+
+    let provider_items: Vec<ListItem> = app
+        .providers
+        .iter()
+        .map(|p| {
+            ListItem::new(Line::from(vec![
+                Span::raw(if app.dense {
+                    format!("{} ", p.name)
+                } else {
+                    format!(" {} ", p.name)
+                }),
+                Span::styled(
+                    format!("({}, {})", p.vehicle_type, p.proxies.len()),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let providers = List::new(provider_items)
+        .block(
+            Block::default()
+                .borders(pane_borders(app))
+                .title(format!(
+                    " Providers ({}) ",
+                    position_label(&app.provider_state, app.providers.len())
+                ))
+                .border_style(focused(0)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("▎");
+    f.render_stateful_widget(providers, chunks[0], &mut app.provider_state);
+    if app.providers.is_empty() {
+        render_placeholder(f, chunks[0], "No proxy providers");
+    }
+
+    let nodes: &[Proxy] = app
+        .provider_state
+        .selected()
+        .and_then(|i| app.providers.get(i))
+        .map(|p| p.proxies.as_slice())
+        .unwrap_or(&[]);
+    let no_nodes = nodes.is_empty() && !app.providers.is_empty();
+    let (name_width, delay_width) = node_column_widths(app);
+    let node_items: Vec<ListItem> = nodes
+        .iter()
+        .map(|node| {
+            let delay = node.history.last().map(|h| h.delay).unwrap_or(-1);
+            let delay_str = if delay > 0 {
+                format!("{}ms", delay)
+            } else {
+                "---".to_string()
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(
+                    " {:<w$} ",
+                    truncate_chars(&node.name, name_width),
+                    w = name_width
+                )),
+                Span::styled(
+                    if node.udp { "U " } else { "  " },
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::styled(
+                    format!("{:<12} ", node.proxy_type),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{:>w$} ", delay_str, w = delay_width),
+                    Style::default().fg(delay_color(delay)),
+                ),
+            ]))
+        })
+        .collect();
+
+    let nodes_title = format!(
+        " {} ({}) ",
+        app.selected_provider()
+            .map(|p| p.name.as_str())
+            .unwrap_or("Nodes"),
+        position_label(&app.provider_node_state, nodes.len())
+    );
+    let node_list = List::new(node_items)
+        .block(
+            Block::default()
+                .borders(pane_borders(app))
+                .title(nodes_title)
+                .border_style(focused(1)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("▎");
+    f.render_stateful_widget(node_list, chunks[1], &mut app.provider_node_state);
+    if no_nodes {
+        render_placeholder(f, chunks[1], "No nodes in this provider");
+    }
 }
 
 fn render_rules_tab(f: &mut Frame, app: &mut App, area: Rect) {
@@ -922,11 +2957,10 @@ fn render_rules_tab(f: &mut Frame, app: &mut App, area: Rect) {
         .collect();
 
     let rules = List::new(rule_items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!(" Rules ({}) ", app.rules.len())),
-        )
+        .block(Block::default().borders(pane_borders(app)).title(format!(
+            " Rules ({}) ",
+            position_label(&app.rule_state, app.rules.len())
+        )))
         .highlight_style(
             Style::default()
                 .bg(Color::Rgb(50, 50, 50))
@@ -935,11 +2969,19 @@ fn render_rules_tab(f: &mut Frame, app: &mut App, area: Rect) {
         .highlight_symbol("▎");
 
     f.render_stateful_widget(rules, area, &mut app.rule_state);
+    if app.rules.is_empty() {
+        render_placeholder(f, area, "No rules");
+    }
 }
 
 fn render_conns_tab(f: &mut Frame, app: &mut App, area: Rect) {
-    let conn_items: Vec<ListItem> = app
-        .conns
+    let chain_query = if app.conn_filter_mode == ConnFilterMode::Chain {
+        app.conn_filter.to_lowercase()
+    } else {
+        String::new()
+    };
+    let visible = app.filtered_conns();
+    let conn_items: Vec<ListItem> = visible
         .iter()
         .map(|c| {
             let host_or_ip = if c.metadata.host.is_empty() {
@@ -948,39 +2990,349 @@ fn render_conns_tab(f: &mut Frame, app: &mut App, area: Rect) {
                 &c.metadata.host
             };
 
-            let chains = if c.chains.is_empty() {
-                "DIRECT".to_string()
+            let (source_width, host_width) = if app.dense { (15, 24) } else { (20, 30) };
+            let mut spans = conn_badges(&c.metadata);
+            spans.extend([
+                Span::styled(
+                    format!("{:<w$} ", c.metadata.source_ip, w = source_width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!(
+                        "{:<w$} ",
+                        truncate_str(host_or_ip, host_width - 2),
+                        w = host_width
+                    ),
+                    Style::default().fg(Color::White),
+                ),
+            ]);
+            spans.extend(chain_spans(&c.chains, &chain_query, 28));
+            spans.push(Span::styled(
+                format!("↓{:<10} ↑{:<10}", format_bytes(c.download), format_bytes(c.upload)),
+                Style::default().fg(Color::Yellow),
+            ));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let placeholder = match (visible.is_empty(), app.conns.is_empty()) {
+        (false, _) => None,
+        (true, true) => Some("No active connections"),
+        (true, false) => Some("No connections match the filter"),
+    };
+    let position = position_label(&app.conn_state, visible.len());
+    let title = if app.conn_filter.is_empty() && !app.filter_editing {
+        format!(" Connections ({}) ", position)
+    } else {
+        format!(
+            " Connections ({} of {}) | {}: {}{} ",
+            position,
+            app.conns.len(),
+            app.conn_filter_mode.label(),
+            app.conn_filter,
+            if app.filter_editing { "▏" } else { "" }
+        )
+    };
+
+    let conns = List::new(conn_items)
+        .block(Block::default().borders(pane_borders(app)).title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("▎");
+
+    f.render_stateful_widget(conns, area, &mut app.conn_state);
+    if let Some(msg) = placeholder {
+        render_placeholder(f, area, msg);
+    }
+}
+
+/// Render a connection's chain (outermost group first), highlighting
+/// elements that match `query` and padding the result to `width + 2` columns.
+fn chain_spans(chains: &[String], query: &str, width: usize) -> Vec<Span<'static>> {
+    let normal = Style::default().fg(Color::Cyan);
+    if chains.is_empty() {
+        return vec![Span::styled(
+            format!("{:<w$} ", "DIRECT", w = width + 2),
+            normal,
+        )];
+    }
+
+    let highlight = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut remaining = width;
+
+    for (i, element) in chains.iter().rev().enumerate() {
+        if i > 0 {
+            if remaining <= 3 {
+                break;
+            }
+            spans.push(Span::styled(" ← ", normal));
+            remaining -= 3;
+        }
+        if remaining == 0 {
+            break;
+        }
+        let text = truncate_chars(element, remaining);
+        remaining -= text.chars().count();
+        let style = if !query.is_empty() && element.to_lowercase().contains(query) {
+            highlight
+        } else {
+            normal
+        };
+        spans.push(Span::styled(text, style));
+    }
+
+    spans.push(Span::raw(" ".repeat(remaining + 2)));
+    spans
+}
+
+/// Short badge for an inbound type; unknown types fall back to their
+/// first letters
+fn inbound_badge(conn_type: &str) -> String {
+    let badge = match conn_type.to_ascii_lowercase().as_str() {
+        "http" => "H",
+        "https" => "HS",
+        "socks4" => "S4",
+        "socks5" => "S5",
+        "mixed" => "MX",
+        "redir" => "RD",
+        "tproxy" => "TP",
+        "tun" => "TUN",
+        "tunnel" => "TNL",
+        "shadowsocks" => "SS",
+        "vmess" => "VM",
+        "inner" => "IN",
+        "dns" => "DNS",
+        _ => return conn_type.chars().take(3).collect::<String>().to_uppercase(),
+    };
+    badge.to_string()
+}
+
+/// Network (T/U) and inbound type badges for a connection
+fn conn_badges(meta: &ConnectionMetadata) -> Vec<Span<'static>> {
+    let (network, color) = match meta.network.to_ascii_lowercase().as_str() {
+        "tcp" => ("T".to_string(), Color::Blue),
+        "udp" => ("U".to_string(), Color::Magenta),
+        other => (
+            other.chars().take(1).collect::<String>().to_uppercase(),
+            Color::Gray,
+        ),
+    };
+    vec![
+        Span::styled(format!("{:<1} ", network), Style::default().fg(color)),
+        Span::styled(
+            format!("{:<3} ", inbound_badge(&meta.conn_type)),
+            Style::default().fg(Color::LightYellow),
+        ),
+    ]
+}
+
+/// Dimmed message centered in an empty list pane
+fn render_placeholder(f: &mut Frame, area: Rect, msg: &str) {
+    if area.height < 3 {
+        return;
+    }
+    let line = Rect {
+        x: area.x + 1,
+        y: area.y + area.height / 2,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+    let text = Paragraph::new(msg)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(text, line);
+}
+
+/// List pane borders; dense mode keeps only the titled top edge
+fn pane_borders(app: &App) -> Borders {
+    if app.dense {
+        Borders::TOP
+    } else {
+        Borders::ALL
+    }
+}
+
+/// Name and delay column widths of node lists
+fn node_column_widths(app: &App) -> (usize, usize) {
+    if app.dense {
+        (24, 6)
+    } else {
+        (30, 8)
+    }
+}
+
+/// Index to select after the group list changed: the previously selected
+/// group where it still exists, otherwise the old index clamped to the new
+/// list.
+fn reselect_group(
+    groups: &[String],
+    previous: Option<&str>,
+    previous_idx: Option<usize>,
+) -> Option<usize> {
+    if groups.is_empty() {
+        return None;
+    }
+    previous
+        .and_then(|name| groups.iter().position(|g| g == name))
+        .or(Some(previous_idx.unwrap_or(0).min(groups.len() - 1)))
+}
+
+/// Keep a list selection within `len` items, selecting the first if unset
+fn clamp_selection(state: &mut ListState, len: usize) {
+    if len == 0 {
+        state.select(None);
+    } else {
+        let i = state.selected().unwrap_or(0);
+        state.select(Some(i.min(len - 1)));
+    }
+}
+
+/// `selected/len` for list titles, e.g. `1203/3571`
+fn position_label(state: &ListState, len: usize) -> String {
+    match state.selected() {
+        Some(i) if len > 0 => format!("{}/{}", (i + 1).min(len), len),
+        _ => format!("0/{}", len),
+    }
+}
+
+fn delay_color(delay: i64) -> Color {
+    if delay < 0 {
+        Color::DarkGray
+    } else if delay < 200 {
+        Color::Green
+    } else if delay < 500 {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+/// Delay color scaled between the best (green) and worst (red) delay of a group
+fn relative_delay_color(delay: i64, min: i64, max: i64) -> Color {
+    if delay <= 0 {
+        return Color::DarkGray;
+    }
+    let t = if max > min {
+        (delay - min) as f64 / (max - min) as f64
+    } else {
+        0.0
+    };
+    // Green → yellow → red
+    let red = (510.0 * t).min(255.0) as u8;
+    let green = (510.0 * (1.0 - t)).min(255.0) as u8;
+    Color::Rgb(red, green, 0)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    const GB: u64 = 1024 * MB;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Format a rate as binary bytes (`MB/s`) or decimal bits (`Mbps`)
+fn format_speed(bytes_per_sec: u64, in_bits: bool) -> String {
+    if !in_bits {
+        return format!("{}/s", format_bytes(bytes_per_sec));
+    }
+    const KBIT: f64 = 1000.0;
+    const MBIT: f64 = 1000.0 * KBIT;
+    const GBIT: f64 = 1000.0 * MBIT;
+
+    let bits = bytes_per_sec as f64 * 8.0;
+    if bits >= GBIT {
+        format!("{:.2} Gbps", bits / GBIT)
+    } else if bits >= MBIT {
+        format!("{:.2} Mbps", bits / MBIT)
+    } else if bits >= KBIT {
+        format!("{:.2} Kbps", bits / KBIT)
+    } else {
+        format!("{} bps", bits)
+    }
+}
+
+/// Compact elapsed time, e.g. `12s`, `3m`, `2h`
+fn format_age(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
+
+fn render_favorites_popup(f: &mut Frame, app: &mut App) {
+    let area = centered_rect(60, 50, f.size());
+
+    let items: Vec<ListItem> = app
+        .state
+        .favorites
+        .iter()
+        .enumerate()
+        .map(|(idx, fav)| {
+            let delay = node_delay(&app.all_proxies, &app.providers, &fav.group, &fav.name);
+            let delay_str = if delay > 0 {
+                format!("{}ms", delay)
+            } else {
+                "---".to_string()
+            };
+            let in_use = app
+                .all_proxies
+                .get(&fav.group)
+                .and_then(|g| g.now.as_ref())
+                .is_some_and(|now| *now == fav.name);
+            let key = if idx < 9 {
+                format!(" {} ", idx + 1)
             } else {
-                c.chains.iter().rev().cloned().collect::<Vec<_>>().join(" ← ")
+                "   ".to_string()
             };
-            
-            let content = Line::from(vec![
-                Span::styled(
-                    format!("{:<20} ", c.metadata.source_ip),
-                    Style::default().fg(Color::DarkGray),
-                ),
+
+            ListItem::new(Line::from(vec![
+                Span::styled(key, Style::default().fg(Color::Cyan)),
                 Span::styled(
-                    format!("{:<30} ", truncate_str(host_or_ip, 28)),
-                    Style::default().fg(Color::White),
+                    if in_use { "● " } else { "  " },
+                    Style::default().fg(Color::Green),
                 ),
                 Span::styled(
-                    format!("{:<30} ", truncate_str(&chains, 28)),
-                    Style::default().fg(Color::Cyan),
+                    format!("{:<20} ", truncate_chars(&fav.group, 20)),
+                    Style::default().fg(Color::DarkGray),
                 ),
+                Span::raw(format!("{:<30} ", truncate_chars(&fav.name, 30))),
                 Span::styled(
-                    format!("↓{:<10} ↑{:<10}", format_bytes(c.download), format_bytes(c.upload)),
-                    Style::default().fg(Color::Yellow),
+                    format!("{:>8}", delay_str),
+                    Style::default().fg(delay_color(delay)),
                 ),
-            ]);
-            ListItem::new(content)
+            ]))
         })
         .collect();
 
-    let conns = List::new(conn_items)
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!(" Connections ({}) ", app.conns.len())),
+                .title(format!(
+                    " Favorites ({}) 1-9/Enter select, Esc close ",
+                    position_label(&app.favorite_state, app.state.favorites.len())
+                ))
+                .border_style(Style::default().fg(Color::Cyan)),
         )
         .highlight_style(
             Style::default()
@@ -989,31 +3341,137 @@ fn render_conns_tab(f: &mut Frame, app: &mut App, area: Rect) {
         )
         .highlight_symbol("▎");
 
-    f.render_stateful_widget(conns, area, &mut app.conn_state);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.favorite_state);
+    if app.state.favorites.is_empty() {
+        render_placeholder(f, area, "No favorites yet (press * on a node)");
+    }
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
+fn render_source_summary_popup(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 60, f.size());
 
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
-    }
+    let summary = app.source_summary();
+    let header = Line::from(Span::styled(
+        format!(
+            "  {:<40} {:>6} {:>12} {:>12}",
+            "Source", "Conns", "Upload", "Download"
+        ),
+        Style::default().fg(Color::DarkGray),
+    ));
+    let mut text = vec![header];
+    text.extend(summary.iter().map(|(ip, count, up, down)| {
+        Line::from(format!(
+            "  {:<40} {:>6} {:>12} {:>12}",
+            ip,
+            count,
+            format_bytes(*up),
+            format_bytes(*down)
+        ))
+    }));
+
+    let title = format!(
+        " Sources ({} sources, {} conns) (S/Esc close) ",
+        summary.len(),
+        summary.iter().map(|s| s.1).sum::<usize>()
+    );
+    let popup = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
 }
 
-fn format_speed(bytes_per_sec: u64) -> String {
-    format!("{}/s", format_bytes(bytes_per_sec))
+fn render_config_popup(f: &mut Frame, config: &ConfigResponse) {
+    let area = centered_rect(50, 40, f.size());
+
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(
+                format!("  {:<14}", label),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ])
+    };
+    let or_unknown = |value: Option<String>| value.unwrap_or_else(|| "N/A".to_string());
+
+    let text = vec![
+        Line::from(""),
+        row("Mode", config.mode.clone()),
+        row("HTTP port", config.http_port.to_string()),
+        row("SOCKS port", config.socks_port.to_string()),
+        row(
+            "Allow LAN",
+            or_unknown(config.allow_lan.map(|v| v.to_string())),
+        ),
+        row("Bind address", or_unknown(config.bind_address.clone())),
+        row("Log level", or_unknown(config.log_level.clone())),
+    ];
+
+    let popup = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Core Config (c/Esc close) ")
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
 }
 
-fn render_help_popup(f: &mut Frame) {
-    let area = centered_rect(60, 70, f.size());
+fn render_close_confirm_popup(f: &mut Frame, app: &App, count: usize) {
+    let area = centered_rect(40, 20, f.size());
+
+    let scope = if app.conn_filter.is_empty() {
+        "all connections".to_string()
+    } else {
+        format!(
+            "{} matching \"{}\"",
+            app.conn_filter_mode.label(),
+            app.conn_filter
+        )
+    };
+    let text = vec![
+        Line::from(""),
+        Line::from(format!("  Close {} connection(s)?", count)),
+        Line::from(Span::styled(
+            format!("  ({})", scope),
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+        Line::from("  y: close   any other key: cancel"),
+    ];
+
+    let popup = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Close Connections ")
+            .border_style(Style::default().fg(Color::Red)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_help_popup(f: &mut Frame, page: usize, tabs: &[Tab]) {
+    if page == 1 {
+        render_legend_popup(f);
+        return;
+    }
+    let area = centered_rect(60, 90, f.size());
+
+    // Number keys follow the configured tab order
+    let keys = match tabs.len() {
+        1 => "1".to_string(),
+        n => format!("1-{}", n),
+    };
+    let titles: Vec<&str> = tabs.iter().map(|t| t.title()).collect();
+    let tab_line = format!("  {:<10}Switch tabs ({})", keys, titles.join("/"));
 
     let help_text = vec![
         "",
@@ -1024,19 +3482,39 @@ fn render_help_popup(f: &mut Frame) {
         "  ←/h       Prev tab / Focus groups",
         "  →/l       Next tab / Focus proxies",
         "  Tab       Switch focus",
-        "  1-3       Switch tabs (Proxies/Rules/Conns)",
+        "  </>       Narrow/widen the groups pane",
+        &tab_line,
         "",
         "  Actions",
         "  ──────────────────────────────",
-        "  Enter     Select proxy",
+        "  Enter     Select proxy (twice in auto groups)",
         "  t         Test delay for selected proxy",
+        "  T         Test delay for all proxies in group / provider",
+        "  A         Re-test auto group so it re-elects",
+        "  e         Jump to next timed-out proxy",
+        "  C         Toggle relative delay colors",
+        "  *         Star/unstar selected proxy",
+        "  F         Favorites (select with 1-9)",
         "  m         Switch mode (Rule/Global/Direct)",
+        "  M         Switch mode in reverse (Rule/Direct/Global)",
+        "  d         Toggle Direct mode / restore previous mode",
         "  u         Refresh subscriptions",
+        "  H         Health-check all proxy providers",
         "  r         Refresh data",
+        "  c         Show core config",
+        "  b         Toggle speed units (bytes/bits)",
+        "  z         Toggle dense mode",
+        "  /         Search proxies (name/protocol) or filter connections",
+        "  p         Cycle proxy protocol filter",
+        "  f         Cycle connection filter (host/chain/rule/source)",
+        "  S         Traffic summary per source IP",
+        "  g         Go to the group carrying the connection",
+        "  X         Close all connections in the filtered view",
         "",
         "  General",
         "  ──────────────────────────────",
         "  ?         Toggle this help",
+        "  Tab/←/→   Show color legend",
         "  q/Esc     Quit",
         "",
     ];
@@ -1045,7 +3523,7 @@ fn render_help_popup(f: &mut Frame) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Help ")
+                .title(" Help (1/2) ")
                 .border_style(Style::default().fg(Color::Cyan)),
         )
         .style(Style::default().fg(Color::White));
@@ -1054,6 +3532,85 @@ fn render_help_popup(f: &mut Frame) {
     f.render_widget(help, area);
 }
 
+fn render_legend_popup(f: &mut Frame) {
+    let area = centered_rect(60, 70, f.size());
+
+    let entry = |sample: &'static str, color: Color, text: &'static str| {
+        Line::from(vec![
+            Span::raw("  "),
+            Span::styled(format!("{:<10}", sample), Style::default().fg(color)),
+            Span::raw(text),
+        ])
+    };
+    let heading = |text: &'static str| Line::from(format!("  {}", text));
+    let rule = || Line::from("  ──────────────────────────────");
+
+    let legend_text = vec![
+        Line::from(""),
+        heading("Delays"),
+        rule(),
+        entry("120ms", Color::Green, "Fast (under 200ms)"),
+        entry("350ms", Color::Yellow, "Medium (200-500ms)"),
+        entry("800ms", Color::Red, "Slow (500ms and above)"),
+        entry("---", Color::DarkGray, "Untested or timed out"),
+        entry(
+            "C",
+            Color::White,
+            "Scale colors from the group's best to worst",
+        ),
+        Line::from(""),
+        heading("Markers"),
+        rule(),
+        entry("●", Color::Green, "Node currently used by the group"),
+        entry("★", Color::Yellow, "Favorite node"),
+        entry("U", Color::Magenta, "Node supports UDP"),
+        entry(
+            "?",
+            Color::LightRed,
+            "Name shared by nodes in several providers",
+        ),
+        entry("border", Color::Cyan, "Focused pane"),
+        Line::from(""),
+        heading("Connections"),
+        rule(),
+        entry("T / U", Color::Blue, "TCP / UDP connection"),
+        entry(
+            "S5 H TUN",
+            Color::LightYellow,
+            "Inbound: SOCKS5, HTTP, TUN, ...",
+        ),
+        entry("source", Color::DarkGray, "Source IP"),
+        entry("host", Color::White, "Destination host or IP"),
+        entry("chain", Color::Cyan, "Proxy chain (outermost group first)"),
+        Line::from(vec![
+            Span::raw("  "),
+            Span::styled(
+                "match",
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("     Chain element matching the filter"),
+        ]),
+        entry("↓ ↑", Color::Yellow, "Downloaded / uploaded bytes"),
+        Line::from(""),
+        heading("Tab/←/→ Back to keys"),
+    ];
+
+    let legend = Paragraph::new(legend_text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Color Legend (2/2) ")
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().fg(Color::White));
+
+    f.render_widget(Clear, area);
+    f.render_widget(legend, area);
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -1074,6 +3631,16 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
+fn truncate_chars(s: &str, max_chars: usize) -> String {
+    if s.chars().count() > max_chars {
+        let mut out: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    } else {
+        s.to_string()
+    }
+}
+
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() > max_len {
         format!("{}…", &s[..max_len - 1])
@@ -1088,23 +3655,30 @@ fn truncate_str(s: &str, max_len: usize) -> String {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let client = ClashClient::new(&cli.controller, cli.secret.clone());
+    let mut config = Config::load()?;
+    config.read_only |= cli.read_only;
+    config.no_mouse |= cli.no_mouse;
+    let secret = resolve_secret(&cli, &config)?;
+    let logger = Logger::open(cli.log_file.as_deref())?;
+    let client = ClashClient::new(
+        &cli.controller,
+        secret,
+        Duration::from_secs(cli.timeout.max(1)),
+    );
 
     // Start traffic monitoring thread
     let (traffic_tx, traffic_rx) = crossbeam_channel::unbounded();
-    let controller = cli.controller.clone();
-    let secret = cli.secret.clone();
-    
-    thread::spawn(move || {
-        let ws_url = format!("ws://{}/traffic", controller);
-        let ws_url = if let Some(s) = secret {
-            format!("{}?token={}", ws_url, urlencoding::encode(&s))
-        } else {
-            ws_url
-        };
+    let ws_client = client.clone();
+    let ws_logger = logger.clone();
 
+    thread::spawn(move || {
+        let mut last_auth = None;
         loop {
-            if let Ok((mut socket, _)) = connect(&ws_url) {
+            if let Ok((mut socket, auth)) = ws_client.connect_ws("/traffic") {
+                if last_auth != Some(auth) {
+                    ws_logger.log(&format!("Traffic websocket connected (auth: {})", auth));
+                    last_auth = Some(auth);
+                }
                 while let Ok(msg) = socket.read() {
                     if msg.is_text() || msg.is_binary() {
                         let data = msg.into_data();
@@ -1150,19 +3724,17 @@ fn main() -> Result<()> {
         }
         Some(Commands::Groups) => {
             let resp = client.get_proxies()?;
-            let groups: Vec<_> = resp
-                .proxies
-                .iter()
-                .filter(|(_, p)| {
-                    p.proxy_type == "Selector"
-                        || p.proxy_type == "URLTest"
-                        || p.proxy_type == "Fallback"
-                })
-                .collect();
+            let groups = ordered_groups(&resp.proxies);
 
             println!("Proxy Groups ({}):", groups.len());
-            for (name, proxy) in groups {
-                let current = proxy.now.as_deref().unwrap_or("N/A");
+            for name in &groups {
+                let proxy = &resp.proxies[name];
+                let chain = resolve_now_chain(&resp.proxies, name);
+                let current = if chain.is_empty() {
+                    "N/A".to_string()
+                } else {
+                    chain.join(" → ")
+                };
                 println!(
                     "  [{:<12}] {:<20} → {}",
                     proxy.proxy_type, name, current
@@ -1170,21 +3742,39 @@ fn main() -> Result<()> {
             }
         }
         Some(Commands::Version) => {
-            let resp = client.get_version()?;
-            println!("Clash version: {}", resp.version);
+            println!("clashtui version: {}", env!("CARGO_PKG_VERSION"));
+            match client.get_version() {
+                Ok(resp) => {
+                    let flavor = if resp.meta {
+                        " (Meta)"
+                    } else if resp.premium {
+                        " (Premium)"
+                    } else {
+                        ""
+                    };
+                    println!("core version:     {}{}", resp.version, flavor);
+                }
+                Err(e) => println!("core version:     unavailable ({:#})", e),
+            }
         }
         Some(Commands::Refresh) => {
+            if config.read_only {
+                anyhow::bail!("read-only mode: refusing to refresh subscriptions");
+            }
             let proxy_provider_names = client.get_proxy_provider_names().unwrap_or_default();
             let rule_provider_names = client.get_rule_provider_names().unwrap_or_default();
 
             let mut updated_count: usize = 0;
             let mut failed_items: Vec<String> = Vec::new();
 
+            let mut pending_items: Vec<String> = Vec::new();
+
             for provider_name in proxy_provider_names {
-                if let Err(err) = client.update_proxy_provider(&provider_name) {
-                    failed_items.push(format!("proxy:{} ({})", provider_name, err));
-                } else {
-                    updated_count += 1;
+                match client.update_proxy_provider_and_wait(&provider_name, PROVIDER_UPDATE_TIMEOUT)
+                {
+                    Ok(true) => updated_count += 1,
+                    Ok(false) => pending_items.push(provider_name),
+                    Err(err) => failed_items.push(format!("proxy:{} ({})", provider_name, err)),
                 }
             }
 
@@ -1197,6 +3787,12 @@ fn main() -> Result<()> {
             }
 
             println!("Refreshed {} provider(s)", updated_count);
+            if !pending_items.is_empty() {
+                println!("Not yet confirmed by the core ({}):", pending_items.len());
+                for item in pending_items {
+                    println!("  - {}", item);
+                }
+            }
             if !failed_items.is_empty() {
                 println!("Errors ({}):", failed_items.len());
                 for item in failed_items {
@@ -1204,32 +3800,187 @@ fn main() -> Result<()> {
                 }
             }
         }
+        Some(Commands::Status) => {
+            let timeout = client.timeout().min(STATUS_TIMEOUT);
+            let client = client.with_timeout(timeout);
+            let core_config = client.get_config()?;
+            let conns = client.get_connections().ok();
+            let groups = client
+                .get_proxies()?
+                .proxies
+                .values()
+                .filter(|p| {
+                    p.proxy_type == "Selector"
+                        || p.proxy_type == "URLTest"
+                        || p.proxy_type == "Fallback"
+                })
+                .count();
+            let summary = StatusSummary {
+                mode: core_config.mode,
+                http_port: core_config.http_port,
+                socks_port: core_config.socks_port,
+                connections: conns.as_ref().map(|c| c.connections.len()),
+                upload_total: conns.as_ref().map(|c| c.upload_total),
+                download_total: conns.as_ref().map(|c| c.download_total),
+                groups,
+            };
+
+            if cli.json {
+                println!("{}", serde_json::to_string(&summary)?);
+            } else {
+                let or_na = |v: Option<String>| v.unwrap_or_else(|| "N/A".to_string());
+                println!("Mode:        {}", summary.mode);
+                println!("HTTP port:   {}", summary.http_port);
+                println!("SOCKS port:  {}", summary.socks_port);
+                println!(
+                    "Connections: {}",
+                    or_na(summary.connections.map(|n| n.to_string()))
+                );
+                println!(
+                    "Upload:      {}",
+                    or_na(summary.upload_total.map(format_bytes))
+                );
+                println!(
+                    "Download:    {}",
+                    or_na(summary.download_total.map(format_bytes))
+                );
+                println!("Groups:      {}", summary.groups);
+            }
+        }
+        Some(Commands::Metrics { ref listen }) => {
+            serve_metrics(&client, &traffic_rx, listen)?;
+        }
         Some(Commands::Tui) | None => {
-            run_tui(client, traffic_rx)?;
+            if let Some(ref path) = cli.stats_file {
+                let interval = Duration::from_secs(cli.stats_interval.max(1));
+                spawn_stats_logger(client.clone(), path, interval)?;
+            }
+            run_tui(client, traffic_rx, config, logger)?;
         }
     }
 
     Ok(())
 }
 
-fn run_tui(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>) -> Result<()> {
+/// Stream connection snapshots from the `/connections` websocket, polling
+/// the REST endpoint instead while the websocket cannot be opened and the
+/// Conns tab is shown.
+fn spawn_conns_stream(
+    client: ClashClient,
+    logger: Logger,
+    visible: Arc<AtomicBool>,
+) -> crossbeam_channel::Receiver<ConnectionsResponse> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        let mut streaming = None;
+        loop {
+            match client.connect_ws("/connections") {
+                Ok((mut socket, _)) => {
+                    if streaming != Some(true) {
+                        logger.log("Connections websocket connected");
+                        streaming = Some(true);
+                    }
+                    while let Ok(msg) = socket.read() {
+                        if msg.is_text() || msg.is_binary() {
+                            let data = msg.into_data();
+                            if let Ok(resp) = serde_json::from_slice::<ConnectionsResponse>(&data) {
+                                if tx.send(resp).is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(_) => {
+                    if streaming != Some(false) {
+                        logger.log("Connections websocket unavailable; polling instead");
+                        streaming = Some(false);
+                    }
+                    if visible.load(Ordering::Relaxed) {
+                        if let Ok(resp) = client.get_connections() {
+                            if tx.send(resp).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+            thread::sleep(CONN_POLL_INTERVAL);
+        }
+    });
+    rx
+}
+
+fn run_tui(
+    client: ClashClient,
+    traffic_rx: crossbeam_channel::Receiver<Traffic>,
+    config: Config,
+    logger: Logger,
+) -> Result<()> {
+    let mouse = !config.no_mouse;
+
+    // Restore the terminal before printing a panic so the shell stays usable
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        if mouse {
+            let _ = execute!(io::stdout(), DisableMouseCapture);
+        }
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+        default_hook(info);
+    }));
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
-    let mut app = App::new(client, traffic_rx);
+    let mut app = App::new(client, traffic_rx, config, logger);
 
     // Main loop
     loop {
+        app.tick();
         terminal.draw(|f| ui(f, &mut app))?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
+                    if app.filter_editing {
+                        app.handle_filter_key(key.code);
+                        continue;
+                    }
+                    if app.show_favorites {
+                        app.handle_favorites_key(key.code);
+                        continue;
+                    }
+                    if app.show_source_summary {
+                        if matches!(
+                            key.code,
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('S')
+                        ) {
+                            app.show_source_summary = false;
+                        }
+                        continue;
+                    }
+                    if app.pending_close.is_some() {
+                        app.handle_close_confirm_key(key.code);
+                        continue;
+                    }
+                    if app.core_config.is_some() {
+                        if matches!(
+                            key.code,
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('c')
+                        ) {
+                            app.core_config = None;
+                        }
+                        continue;
+                    }
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => {
                             if app.show_help {
@@ -1240,32 +3991,26 @@ fn run_tui(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>
                         }
                         KeyCode::Char('?') => {
                             app.show_help = !app.show_help;
+                            app.help_page = 0;
                         }
-                        KeyCode::Char('1') => {
-                            app.current_tab = Tab::Proxies;
-                            app.refresh_data();
-                        }
-                        KeyCode::Char('2') => {
-                            app.current_tab = Tab::Rules;
-                            app.refresh_data();
-                        }
-                        KeyCode::Char('3') => {
-                            app.current_tab = Tab::Conns;
-                            app.refresh_data();
+                        KeyCode::Char(c @ '1'..='4') => {
+                            app.switch_to_nth_tab(c as usize - '0' as usize);
                         }
                         KeyCode::Tab => {
                             if app.show_help {
+                                app.help_page = 1 - app.help_page;
                                 continue;
                             }
                             app.toggle_focus();
                         }
                         KeyCode::Left | KeyCode::Char('h') => {
                             if app.show_help {
+                                app.help_page = 1 - app.help_page;
                                 continue;
                             }
                             // 如果在 Proxies Tab 且 Focus 在右侧（代理列表），左键回到组列表
                             // 否则切换到上一个 Tab
-                            if app.current_tab == Tab::Proxies && app.focus == 1 {
+                            if app.has_panes() && app.focus == 1 {
                                 app.focus = 0;
                             } else {
                                 app.prev_tab();
@@ -1273,11 +4018,14 @@ fn run_tui(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>
                         }
                         KeyCode::Right | KeyCode::Char('l') => {
                             if app.show_help {
+                                app.help_page = 1 - app.help_page;
                                 continue;
                             }
                             // 如果在 Proxies Tab 且 Focus 在左侧（组列表），右键进入代理列表
                             // 否则切换到下一个 Tab
                             if app.current_tab == Tab::Proxies && app.focus == 0 {
+                                app.focus_proxies();
+                            } else if app.current_tab == Tab::Providers && app.focus == 0 {
                                 app.focus = 1;
                             } else {
                                 app.next_tab();
@@ -1310,6 +4058,18 @@ fn run_tui(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>
                             }
                             if app.current_tab == Tab::Proxies && app.focus == 1 {
                                 app.test_selected_delay();
+                            } else if app.current_tab == Tab::Providers && app.focus == 1 {
+                                app.test_provider_node();
+                            }
+                        }
+                        KeyCode::Char('T') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies {
+                                app.test_group_delay();
+                            } else if app.current_tab == Tab::Providers {
+                                app.healthcheck_selected_provider();
                             }
                         }
                         KeyCode::Char('r') => {
@@ -1324,13 +4084,150 @@ fn run_tui(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>
                             }
                             app.toggle_mode();
                         }
+                        KeyCode::Char('M') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.toggle_mode_reverse();
+                        }
+                        KeyCode::Char('d') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.toggle_direct();
+                        }
+                        KeyCode::Char('/') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.start_filter();
+                        }
+                        KeyCode::Char('f') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Conns {
+                                app.cycle_conn_filter_mode();
+                            }
+                        }
+                        KeyCode::Char('g') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Conns {
+                                app.jump_to_conn_group();
+                            }
+                        }
+                        KeyCode::Char('S') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Conns {
+                                app.show_source_summary = true;
+                            }
+                        }
+                        KeyCode::Char('X') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Conns {
+                                app.confirm_close_filtered();
+                            }
+                        }
+                        KeyCode::Char('p') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies {
+                                app.cycle_proxy_type_filter();
+                            }
+                        }
                         KeyCode::Char('u') => {
                             if app.show_help {
                                 continue;
                             }
-                            app.status = "Refreshing subscriptions...".to_string();
                             app.refresh_subscriptions();
                         }
+                        KeyCode::Char('<') | KeyCode::Char('>') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies {
+                                let step = GROUP_PANE_STEP as i16;
+                                app.resize_group_pane(if key.code == KeyCode::Char('<') {
+                                    -step
+                                } else {
+                                    step
+                                });
+                            }
+                        }
+                        KeyCode::Char('b') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.toggle_speed_unit();
+                        }
+                        KeyCode::Char('C') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies {
+                                app.relative_delays = !app.relative_delays;
+                                app.set_status(if app.relative_delays {
+                                    "Delay colors relative to this group"
+                                } else {
+                                    "Delay colors use fixed thresholds"
+                                });
+                            }
+                        }
+                        KeyCode::Char('z') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.toggle_dense();
+                        }
+                        KeyCode::Char('A') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies {
+                                app.reelect_group();
+                            }
+                        }
+                        KeyCode::Char('e') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies {
+                                app.jump_to_next_timeout();
+                            }
+                        }
+                        KeyCode::Char('*') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            if app.current_tab == Tab::Proxies && app.focus == 1 {
+                                app.toggle_favorite();
+                            }
+                        }
+                        KeyCode::Char('F') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.open_favorites();
+                        }
+                        KeyCode::Char('c') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.open_core_config();
+                        }
+                        KeyCode::Char('H') => {
+                            if app.show_help {
+                                continue;
+                            }
+                            app.healthcheck_all_providers();
+                        }
                         _ => {}
                     }
                 }
@@ -1340,12 +4237,36 @@ fn run_tui(client: ClashClient, traffic_rx: crossbeam_channel::Receiver<Traffic>
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    if mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_survives_shrinking_group_list() {
+        let names = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let before = names(&["Proxy", "Auto", "Streaming", "Netflix", "GLOBAL"]);
+
+        // The selected group moved up after another was removed
+        let after = names(&["Proxy", "Netflix", "GLOBAL"]);
+        assert_eq!(reselect_group(&after, Some("Netflix"), Some(3)), Some(1));
+
+        // The selected group is gone; the index is clamped into range
+        let after = names(&["Proxy", "GLOBAL"]);
+        assert_eq!(reselect_group(&after, Some("Netflix"), Some(3)), Some(1));
+
+        // Nothing left to select
+        assert_eq!(reselect_group(&[], Some("Netflix"), Some(3)), None);
+
+        // First load selects the first group
+        assert_eq!(reselect_group(&before, None, None), Some(0));
+    }
+}